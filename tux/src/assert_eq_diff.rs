@@ -0,0 +1,149 @@
+/// Asserts that two expressions are equal, like [`assert_eq!`], but outputs
+/// a line diff of the values on failure.
+///
+/// Both values are pretty-printed using their [`Debug`](std::fmt::Debug)
+/// implementation (i.e. `{:#?}`) and compared using [`diff::lines`](fn@crate::diff::lines).
+/// This makes it easier to spot the differences between large values.
+///
+/// Like [`assert_eq!`], this supports a custom message.
+///
+/// This macro is enabled by the `diff` feature (enabled by default).
+///
+/// ```should_panic
+/// # use tux::assert_eq_diff;
+/// #[derive(Debug, PartialEq)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let a = Point { x: 1, y: 2 };
+/// let b = Point { x: 1, y: 3 };
+/// assert_eq_diff!(a, b, "points should be equal");
+/// ```
+///
+/// This will panic with the message:
+///
+/// ```text
+/// assertion `left == right` failed: points should be equal
+/// diff (-left +right):
+///  Point {
+///      x: 1,
+/// -    y: 2,
+/// +    y: 3,
+///  }
+/// ```
+#[macro_export]
+macro_rules! assert_eq_diff {
+	($left:expr, $right:expr $(,)?) => {
+		match (&$left, &$right) {
+			(left_val, right_val) => {
+				if !(*left_val == *right_val) {
+					$crate::assert_eq_diff::assert_eq_diff_failed(left_val, right_val, None);
+				}
+			}
+		}
+	};
+	($left:expr, $right:expr, $($arg:tt)+) => {
+		match (&$left, &$right) {
+			(left_val, right_val) => {
+				if !(*left_val == *right_val) {
+					$crate::assert_eq_diff::assert_eq_diff_failed(
+						left_val,
+						right_val,
+						Some(format_args!($($arg)+)),
+					);
+				}
+			}
+		}
+	};
+}
+
+/// Panics with the diff between `left` and `right`. This is used by the
+/// [`assert_eq_diff!`] macro and is not meant to be called directly.
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_eq_diff_failed<T, U>(left: &T, right: &U, args: Option<std::fmt::Arguments>) -> !
+where
+	T: std::fmt::Debug + ?Sized,
+	U: std::fmt::Debug + ?Sized,
+{
+	let left = format!("{:#?}", left);
+	let right = format!("{:#?}", right);
+	let left = left.lines().collect::<Vec<_>>();
+	let right = right.lines().collect::<Vec<_>>();
+	let diff = crate::diff::lines(&left, &right);
+	let diff = if diff.is_empty() {
+		// values that are not equal can still have the same debug output
+		String::from(" (values have the same debug output)")
+	} else {
+		format!(":\n{}", diff)
+	};
+
+	match args {
+		Some(args) => panic!(
+			"assertion `left == right` failed: {}\ndiff (-left +right){}",
+			args, diff
+		),
+		None => panic!(
+			"assertion `left == right` failed\ndiff (-left +right){}",
+			diff
+		),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_panic;
+
+	#[derive(Debug, PartialEq)]
+	struct Item {
+		name: &'static str,
+		value: f64,
+	}
+
+	#[test]
+	fn succeeds_if_equal() {
+		assert_eq_diff!(1, 1);
+		assert_eq_diff!("abc", "abc", "with message");
+		assert_eq_diff!(vec![1, 2, 3], [1, 2, 3]);
+	}
+
+	#[test]
+	fn fails_with_diff_of_debug_output() {
+		let a = Item {
+			name: "a",
+			value: 1.0,
+		};
+		let b = Item {
+			name: "a",
+			value: 2.0,
+		};
+		let expected = crate::text::join_lines([
+			"diff (-left +right):",
+			" Item {",
+			"     name: \"a\",",
+			"-    value: 1.0,",
+			"+    value: 2.0,",
+			" }",
+		]);
+		let run = move || assert_eq_diff!(a, b);
+		let err = std::panic::catch_unwind(run).unwrap_err();
+		let message = err.downcast::<String>().unwrap();
+		assert!(
+			message.ends_with(&expected),
+			"unexpected message: {}",
+			message
+		);
+	}
+
+	#[test]
+	fn fails_with_custom_message() {
+		assert_panic!("failed: custom message 123\ndiff" in assert_eq_diff!(1, 2, "custom message {}", 123));
+	}
+
+	#[test]
+	fn fails_for_values_with_the_same_debug_output() {
+		assert_panic!("same debug output" in assert_eq_diff!(f64::NAN, f64::NAN));
+	}
+}
@@ -0,0 +1,178 @@
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Controls whether the diff output generated by the library uses ANSI
+/// colors (e.g. the `testdata` failure output).
+///
+/// See [`set_color_mode`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+	/// Use colors only if the output is a terminal and the `NO_COLOR`
+	/// environment variable is not set. This is the default.
+	///
+	/// The Rust test harness captures the output of each test without
+	/// changing the output file, so colors are also disabled when running
+	/// inside a test with the output captured. Since the harness does not
+	/// expose whether the output is captured, this is detected from the test
+	/// thread and the `--nocapture` argument (or `RUST_TEST_NOCAPTURE`), and
+	/// may not work for custom harnesses.
+	///
+	/// Colors can be forced on or off with `CARGO_TERM_COLOR` set to `always`
+	/// or `never`, or forced on with `CLICOLOR_FORCE` set to a value other
+	/// than `0`. `NO_COLOR` takes precedence over both.
+	Auto,
+
+	/// Always use colors.
+	Always,
+
+	/// Never use colors.
+	Never,
+}
+
+static COLOR_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Sets the [`ColorMode`] for the diff output generated by the library.
+///
+/// This is a global setting that applies to all threads.
+pub fn set_color_mode(mode: ColorMode) {
+	let value = match mode {
+		ColorMode::Auto => 0,
+		ColorMode::Always => 1,
+		ColorMode::Never => 2,
+	};
+	COLOR_MODE.store(value, Ordering::Relaxed);
+}
+
+/// Returns the current [`ColorMode`] set by [`set_color_mode`].
+pub fn color_mode() -> ColorMode {
+	match COLOR_MODE.load(Ordering::Relaxed) {
+		1 => ColorMode::Always,
+		2 => ColorMode::Never,
+		_ => ColorMode::Auto,
+	}
+}
+
+/// Returns `true` if diff output written to stderr should use colors,
+/// according to the current [`ColorMode`].
+///
+/// In [`ColorMode::Auto`], colors are used when stderr is a terminal and the
+/// output is not captured by the test harness, unless overridden by the
+/// environment (see [`ColorMode::Auto`] for details).
+pub fn use_color_for_stderr() -> bool {
+	let from_env = color_from_env(|name| std::env::var(name).ok());
+	resolve_color(color_mode(), from_env, is_test_output_captured(), || {
+		std::io::stderr().is_terminal()
+	})
+}
+
+/// Resolves whether to use colors for the given mode. In [`ColorMode::Auto`]
+/// the setting from the environment takes precedence, and the terminal check
+/// is only done when the output is not captured.
+fn resolve_color<F: FnOnce() -> bool>(
+	mode: ColorMode,
+	from_env: Option<bool>,
+	captured: bool,
+	is_terminal: F,
+) -> bool {
+	match mode {
+		ColorMode::Always => true,
+		ColorMode::Never => false,
+		ColorMode::Auto => from_env.unwrap_or_else(|| !captured && is_terminal()),
+	}
+}
+
+/// Returns the color setting forced by the environment variables, if any.
+fn color_from_env<F: Fn(&str) -> Option<String>>(var: F) -> Option<bool> {
+	let var = |name| var(name).filter(|x| !x.is_empty());
+	if var("NO_COLOR").is_some() {
+		return Some(false);
+	}
+	match var("CARGO_TERM_COLOR").as_deref() {
+		Some("always") => Some(true),
+		Some("never") => Some(false),
+		_ => var("CLICOLOR_FORCE").filter(|x| x != "0").map(|_| true),
+	}
+}
+
+/// Returns `true` if running inside a test with the output captured by the
+/// test harness, in which case the output is only shown for failed tests
+/// and usually ends up in a log.
+fn is_test_output_captured() -> bool {
+	// the test harness runs each test on a thread named after the test
+	let in_test = std::thread::current().name().is_some_and(|x| x != "main");
+	let no_capture = std::env::args().any(|x| x == "--nocapture" || x == "--no-capture")
+		|| std::env::var_os("RUST_TEST_NOCAPTURE").is_some_and(|x| x != "0");
+	in_test && !no_capture
+}
+
+pub(crate) const RESET: &str = "\x1b[0m";
+pub(crate) const BOLD: &str = "\x1b[1m";
+pub(crate) const DIM: &str = "\x1b[2m";
+pub(crate) const RED: &str = "\x1b[31m";
+pub(crate) const GREEN: &str = "\x1b[32m";
+pub(crate) const CYAN: &str = "\x1b[36m";
+pub(crate) const REVERSE: &str = "\x1b[7m";
+pub(crate) const NO_REVERSE: &str = "\x1b[27m";
+
+/// Wraps the text with the given ANSI style.
+pub(crate) fn paint(style: &str, text: &str) -> String {
+	format!("{}{}{}", style, text, RESET)
+}
+
+#[cfg(test)]
+mod test_color {
+	use super::*;
+
+	#[test]
+	fn forced_color_modes_ignore_environment() {
+		assert!(resolve_color(ColorMode::Always, Some(false), true, || {
+			false
+		}));
+		assert!(!resolve_color(ColorMode::Never, Some(true), false, || true));
+	}
+
+	#[test]
+	fn auto_color_mode_requires_terminal() {
+		assert!(resolve_color(ColorMode::Auto, None, false, || true));
+		assert!(!resolve_color(ColorMode::Auto, None, false, || false));
+	}
+
+	#[test]
+	fn auto_color_mode_is_disabled_for_captured_output() {
+		assert!(!resolve_color(ColorMode::Auto, None, true, || true));
+	}
+
+	#[test]
+	fn auto_color_mode_respects_environment() {
+		assert!(!resolve_color(ColorMode::Auto, Some(false), false, || true));
+		assert!(resolve_color(ColorMode::Auto, Some(true), true, || false));
+	}
+
+	#[test]
+	fn reads_color_setting_from_environment() {
+		let from_vars = |vars: &[(&str, &str)]| {
+			color_from_env(|name| {
+				vars.iter()
+					.find(|(var, _)| *var == name)
+					.map(|(_, value)| value.to_string())
+			})
+		};
+		assert_eq!(from_vars(&[]), None);
+		assert_eq!(from_vars(&[("NO_COLOR", "")]), None);
+		assert_eq!(from_vars(&[("NO_COLOR", "1")]), Some(false));
+		assert_eq!(from_vars(&[("CARGO_TERM_COLOR", "always")]), Some(true));
+		assert_eq!(from_vars(&[("CARGO_TERM_COLOR", "never")]), Some(false));
+		assert_eq!(from_vars(&[("CARGO_TERM_COLOR", "auto")]), None);
+		assert_eq!(from_vars(&[("CLICOLOR_FORCE", "1")]), Some(true));
+		assert_eq!(from_vars(&[("CLICOLOR_FORCE", "0")]), None);
+		assert_eq!(
+			from_vars(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]),
+			Some(false)
+		);
+	}
+
+	#[test]
+	fn paint_resets_style() {
+		assert_eq!(paint(RED, "text"), "\x1b[31mtext\x1b[0m");
+	}
+}
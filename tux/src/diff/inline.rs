@@ -0,0 +1,231 @@
+use super::Diff;
+
+/// Granularity of the tokens compared by an [`InlineDiff`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InlineMode {
+	/// Compares words, with each run of whitespace and each punctuation
+	/// character considered a separate token.
+	Words,
+
+	/// Compares individual characters.
+	Chars,
+}
+
+/// Computes the difference between two lines of text at the word level.
+///
+/// See [`InlineDiff`] for the output format.
+///
+/// # Example
+///
+/// ```
+/// use tux::diff;
+///
+/// let diff = diff::words("let value = 123;", "let value = 456;");
+/// assert_eq!(diff.to_string(), "let value = [-123-]{+456+};");
+/// ```
+pub fn words<'a>(source: &'a str, result: &'a str) -> InlineDiff<'a> {
+	inline(source, result, InlineMode::Words)
+}
+
+/// Computes the difference between two lines of text at the character level.
+///
+/// See [`InlineDiff`] for the output format.
+///
+/// # Example
+///
+/// ```
+/// use tux::diff;
+///
+/// let diff = diff::chars("value", "valve");
+/// assert_eq!(diff.to_string(), "val[-u-]{+v+}e");
+/// ```
+pub fn chars<'a>(source: &'a str, result: &'a str) -> InlineDiff<'a> {
+	inline(source, result, InlineMode::Chars)
+}
+
+/// Computes the difference between two lines of text, splitting them into
+/// tokens according to the given `mode`.
+pub fn inline<'a>(source: &'a str, result: &'a str, mode: InlineMode) -> InlineDiff<'a> {
+	let source = tokenize(source, mode);
+	let result = tokenize(result, mode);
+	let items = super::diff_items(&source, &result);
+	InlineDiff {
+		items,
+		source,
+		result,
+	}
+}
+
+/// Result of an intra-line diff between a `source` and `result` text.
+///
+/// This is similar to a [`DiffResult`](super::DiffResult), but the [`Diff`]
+/// items refer to the tokens of the text.
+///
+/// # Diff output
+///
+/// The [`Display`](std::fmt::Display) output combines both texts, with
+/// removed tokens marked as `[-removed-]` and inserted tokens marked as
+/// `{+inserted+}`.
+///
+/// Each side of the diff can also be rendered separately with
+/// [`InlineDiff::source_marked`] and [`InlineDiff::result_marked`].
+pub struct InlineDiff<'a> {
+	items: Vec<Diff>,
+	source: Vec<&'a str>,
+	result: Vec<&'a str>,
+}
+
+impl<'a> InlineDiff<'a> {
+	pub fn is_empty(&self) -> bool {
+		self.items.is_empty()
+	}
+
+	pub fn items(&self) -> &Vec<Diff> {
+		&self.items
+	}
+
+	/// Returns `true` if `source` and `result` have any tokens in common.
+	pub fn has_common_tokens(&self) -> bool {
+		// the diff items are empty when both sides are equal
+		self.is_empty() || self.items.iter().any(|x| matches!(x, Diff::Output(_)))
+	}
+
+	/// Returns the `source` text with the removed tokens marked.
+	pub fn source_marked(&self) -> String {
+		self.render(|text| format!("[-{}-]", text), |_| String::new())
+	}
+
+	/// Returns the `result` text with the inserted tokens marked.
+	pub fn result_marked(&self) -> String {
+		self.render(|_| String::new(), |text| format!("{{+{}+}}", text))
+	}
+
+	/// Renders the diff by mapping the text for each run of deleted and
+	/// inserted tokens. Tokens in common are output as is.
+	pub(crate) fn render<D, I>(&self, mut delete: D, mut insert: I) -> String
+	where
+		D: FnMut(&str) -> String,
+		I: FnMut(&str) -> String,
+	{
+		if self.is_empty() {
+			return self.source.concat();
+		}
+
+		let mut output = String::new();
+		let mut cur_source = 0;
+		let mut cur_result = 0;
+		for item in self.items.iter() {
+			match item {
+				Diff::Output(count) => {
+					output.push_str(&self.source[cur_source..cur_source + count].concat());
+					cur_source += count;
+					cur_result += count;
+				}
+				Diff::Delete(count) => {
+					let text = self.source[cur_source..cur_source + count].concat();
+					output.push_str(&delete(&text));
+					cur_source += count;
+				}
+				Diff::Insert(count) => {
+					let text = self.result[cur_result..cur_result + count].concat();
+					output.push_str(&insert(&text));
+					cur_result += count;
+				}
+			}
+		}
+		output
+	}
+}
+
+impl<'a> std::fmt::Display for InlineDiff<'a> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let output = self.render(
+			|text| format!("[-{}-]", text),
+			|text| format!("{{+{}+}}", text),
+		);
+		write!(f, "{}", output)
+	}
+}
+
+/// Splits the text into tokens, such that concatenating all tokens results
+/// in the original text.
+fn tokenize(text: &str, mode: InlineMode) -> Vec<&str> {
+	let mut tokens = Vec::new();
+	let mut chars = text.char_indices().peekable();
+	while let Some((start, char)) = chars.next() {
+		let mut end = start + char.len_utf8();
+		if mode == InlineMode::Words {
+			let is_word = |c: char| c.is_alphanumeric() || c == '_';
+			let same_class: Option<fn(char) -> bool> = if is_word(char) {
+				Some(is_word)
+			} else if char.is_whitespace() {
+				Some(char::is_whitespace)
+			} else {
+				None
+			};
+			if let Some(same_class) = same_class {
+				while let Some((pos, next)) = chars.next_if(|&(_, c)| same_class(c)) {
+					end = pos + next.len_utf8();
+				}
+			}
+		}
+		tokens.push(&text[start..end]);
+	}
+	tokens
+}
+
+#[cfg(test)]
+mod test_inline {
+	use super::{chars, tokenize, words, InlineMode};
+
+	#[test]
+	fn tokenize_words() {
+		let out = tokenize("let x_1 = (a  + b);", InlineMode::Words);
+		assert_eq!(
+			out,
+			["let", " ", "x_1", " ", "=", " ", "(", "a", "  ", "+", " ", "b", ")", ";"]
+		);
+	}
+
+	#[test]
+	fn tokenize_chars() {
+		let out = tokenize("ab ç", InlineMode::Chars);
+		assert_eq!(out, ["a", "b", " ", "ç"]);
+	}
+
+	#[test]
+	fn equal_text_has_no_markers() {
+		let diff = words("some text", "some text");
+		assert!(diff.is_empty());
+		assert_eq!(diff.to_string(), "some text");
+		assert_eq!(diff.source_marked(), "some text");
+		assert_eq!(diff.result_marked(), "some text");
+	}
+
+	#[test]
+	fn marks_changed_words() {
+		let diff = words("the quick fox", "the slow fox");
+		assert_eq!(diff.to_string(), "the [-quick-]{+slow+} fox");
+		assert_eq!(diff.source_marked(), "the [-quick-] fox");
+		assert_eq!(diff.result_marked(), "the {+slow+} fox");
+	}
+
+	#[test]
+	fn marks_inserted_and_removed_words() {
+		let diff = words("a b c", "a c d");
+		assert_eq!(diff.to_string(), "a [-b -]c{+ d+}");
+	}
+
+	#[test]
+	fn marks_changed_chars() {
+		let diff = chars("value: 123", "value: 124");
+		assert_eq!(diff.to_string(), "value: 12[-3-]{+4+}");
+	}
+
+	#[test]
+	fn has_common_tokens() {
+		assert!(words("a b", "a c").has_common_tokens());
+		assert!(words("a", "a").has_common_tokens());
+		assert!(!words("a", "b").has_common_tokens());
+	}
+}
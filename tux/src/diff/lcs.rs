@@ -2,92 +2,242 @@
 ///
 /// The sequence is returned as a vector of tuples containing the indexes
 /// of common items between both inputs.
+///
+/// # Implementation
+///
+/// This uses the linear space variation of Myers' O(ND) difference algorithm
+/// (see "An O(ND) Difference Algorithm and Its Variations", Eugene W. Myers,
+/// 1986), where `N` is the total length of the inputs and `D` is the size of
+/// the minimum edit script between them.
+///
+/// Memory usage is proportional to `N`, which allows diffing large inputs
+/// without allocating a full `N * M` table.
 pub fn lcs<T>(list_a: &[T], list_b: &[T]) -> Vec<(usize, usize)>
 where
 	T: std::cmp::PartialEq,
 {
-	if list_a.len() == 0 || list_b.len() == 0 {
-		return Vec::new();
-	}
-
-	// First we compute the LCS length between each suffix of A and B
-	// and store them in a flattened matrix in row-major order.
-
-	let mut max_len_for_suffixes = Vec::new();
-	max_len_for_suffixes.resize(list_a.len() * list_b.len(), 0);
-
-	let suffixes = |pos_a, pos_b| pos_a * list_b.len() + pos_b;
-
-	let last_a = list_a.len() - 1;
-	let last_b = list_b.len() - 1;
-
-	for a in (0..list_a.len()).rev() {
-		for b in (0..list_b.len()).rev() {
-			let items_are_equal = list_a[a] == list_b[b];
-			max_len_for_suffixes[suffixes(a, b)] = if items_are_equal {
-				let not_at_the_end = a < last_a && b < last_b;
-				let suffix_len = if not_at_the_end {
-					max_len_for_suffixes[suffixes(a + 1, b + 1)]
-				} else {
-					0
-				};
-				1 + suffix_len
-			} else {
-				let len_skipping_a = if a < last_a {
-					max_len_for_suffixes[suffixes(a + 1, b)]
-				} else {
-					0
-				};
-				let len_skipping_b = if b < last_b {
-					max_len_for_suffixes[suffixes(a, b + 1)]
-				} else {
-					0
-				};
-				std::cmp::max(len_skipping_a, len_skipping_b)
-			};
-		}
+	let mut longest_sequence = Vec::new();
+	if list_a.is_empty() || list_b.is_empty() {
+		return longest_sequence;
 	}
 
-	// We computed the LCS length for all combinations of suffixes between A
-	// and B. Now we use that to build the actual LCS by using the computed
-	// lengths to decide which elements to take when we have a choice.
+	// The `V` arrays from the paper, storing the furthest reaching path in
+	// each diagonal for the forward and reverse searches. These are shared
+	// by all recursive steps, since each step only needs `2 * D` entries.
+	let max_d = max_edit_distance(list_a.len(), list_b.len());
+	let mut forward = Diagonals::new(max_d);
+	let mut reverse = Diagonals::new(max_d);
 
-	let mut longest_sequence = Vec::new();
+	lcs_between(
+		list_a,
+		0,
+		list_b,
+		0,
+		&mut forward,
+		&mut reverse,
+		&mut longest_sequence,
+	);
 
-	// Iterate both lists until we reach the end of either list. When items
-	// between the lists differ, we use the computed LCS length matrix to
-	// decide whether the best solution skips an item from A or B.
-	let mut a = 0;
-	let mut b = 0;
-	while a < list_a.len() && b < list_b.len() {
-		if list_a[a] == list_b[b] {
-			longest_sequence.push((a, b));
-			a += 1;
-			b += 1;
-		} else {
-			let not_at_the_end = a < last_a && b < last_b;
-			if not_at_the_end {
-				let len_skipping_a = max_len_for_suffixes[suffixes(a + 1, b)];
-				let len_skipping_b = max_len_for_suffixes[suffixes(a, b + 1)];
-				if len_skipping_a > len_skipping_b {
-					a += 1;
-				} else {
-					b += 1;
-				}
-			} else {
-				// when we reach the last item of either list, just iterate the
-				// other until we have a match or exhausted both lists
-				let remaining_items_are_from_a = a < last_a;
-				if remaining_items_are_from_a {
-					a += 1;
-				} else {
-					b += 1;
-				}
+	longest_sequence
+}
+
+/// Appends the longest common subsequence between `list_a` and `list_b` to
+/// `output`, using `offset_a` and `offset_b` to generate absolute indexes
+/// since those are sub-slices of the original inputs.
+///
+/// This will divide the problem at the "middle snake" of the edit path
+/// between both lists, and recursively solve each half.
+fn lcs_between<T>(
+	list_a: &[T],
+	offset_a: usize,
+	list_b: &[T],
+	offset_b: usize,
+	forward: &mut Diagonals,
+	reverse: &mut Diagonals,
+	output: &mut Vec<(usize, usize)>,
+) where
+	T: std::cmp::PartialEq,
+{
+	// Common prefixes and suffixes are part of the LCS, so we can skip them
+	// before searching for the middle snake. This also guarantees that the
+	// edit distance is at least two, which ensures that the middle snake
+	// will divide the problem in two smaller ones.
+
+	let prefix_len = common_prefix_len(list_a, list_b);
+	for i in 0..prefix_len {
+		output.push((offset_a + i, offset_b + i));
+	}
+
+	let list_a = &list_a[prefix_len..];
+	let list_b = &list_b[prefix_len..];
+	let offset_a = offset_a + prefix_len;
+	let offset_b = offset_b + prefix_len;
+
+	let suffix_len = common_suffix_len(list_a, list_b);
+	let list_a = &list_a[..list_a.len() - suffix_len];
+	let list_b = &list_b[..list_b.len() - suffix_len];
+
+	// if either list is empty, there is nothing in common between them
+	if !list_a.is_empty() && !list_b.is_empty() {
+		let (split_a, split_b) = find_middle_snake(list_a, list_b, forward, reverse);
+		lcs_between(
+			&list_a[..split_a],
+			offset_a,
+			&list_b[..split_b],
+			offset_b,
+			forward,
+			reverse,
+			output,
+		);
+		lcs_between(
+			&list_a[split_a..],
+			offset_a + split_a,
+			&list_b[split_b..],
+			offset_b + split_b,
+			forward,
+			reverse,
+			output,
+		);
+	}
+
+	let suffix_a = offset_a + list_a.len();
+	let suffix_b = offset_b + list_b.len();
+	for i in 0..suffix_len {
+		output.push((suffix_a + i, suffix_b + i));
+	}
+}
+
+/// Finds the middle snake of the shortest edit path between both lists by
+/// searching simultaneously from the start and from the end of the lists,
+/// until both searches overlap.
+///
+/// Returns the position in both lists where the snake starts. Both lists
+/// must be non-empty and must not share a common prefix or suffix.
+fn find_middle_snake<T>(
+	list_a: &[T],
+	list_b: &[T],
+	forward: &mut Diagonals,
+	reverse: &mut Diagonals,
+) -> (usize, usize)
+where
+	T: std::cmp::PartialEq,
+{
+	let len_a = list_a.len() as isize;
+	let len_b = list_b.len() as isize;
+
+	// Diagonal `k` contains the positions where `x - y == k`, with `x` and
+	// `y` being the positions in A and B, respectively.
+	//
+	// The reverse search uses positions from the end of the lists, so the
+	// forward diagonal `k` corresponds to the reverse diagonal `delta - k`.
+	let delta = len_a - len_b;
+	let delta_is_odd = delta % 2 != 0;
+
+	forward.set(1, 0);
+	reverse.set(1, 0);
+
+	let max_d = max_edit_distance(list_a.len(), list_b.len()) as isize;
+	for d in 0..=max_d {
+		for k in (-d..=d).step_by(2) {
+			let x_start = forward.next_x(k, d);
+			let y_start = x_start - k;
+
+			let mut x = x_start;
+			let mut y = y_start;
+			while x < len_a && y < len_b && list_a[x as usize] == list_b[y as usize] {
+				x += 1;
+				y += 1;
+			}
+			forward.set(k, x);
+
+			// for an odd delta, the paths can only overlap in a forward
+			// search step, after the reverse search covered `d - 1`
+			let reverse_k = delta - k;
+			if delta_is_odd && reverse_k.abs() < d && x + reverse.get(reverse_k) >= len_a {
+				return (x_start as usize, y_start as usize);
+			}
+		}
+
+		for k in (-d..=d).step_by(2) {
+			let mut x = reverse.next_x(k, d);
+			let mut y = x - k;
+			while x < len_a
+				&& y < len_b && list_a[(len_a - x - 1) as usize]
+				== list_b[(len_b - y - 1) as usize]
+			{
+				x += 1;
+				y += 1;
+			}
+			reverse.set(k, x);
+
+			// for an even delta, the paths overlap in a reverse search step
+			let forward_k = delta - k;
+			if !delta_is_odd && forward_k.abs() <= d && x + forward.get(forward_k) >= len_a {
+				return ((len_a - x) as usize, (len_b - y) as usize);
 			}
 		}
 	}
 
-	longest_sequence
+	unreachable!("middle snake not found for LCS")
+}
+
+/// Maximum number of steps in the search for the middle snake, that is, half
+/// of the worst case edit distance between both lists.
+fn max_edit_distance(len_a: usize, len_b: usize) -> usize {
+	(len_a + len_b).div_ceil(2)
+}
+
+fn common_prefix_len<T: std::cmp::PartialEq>(list_a: &[T], list_b: &[T]) -> usize {
+	list_a
+		.iter()
+		.zip(list_b.iter())
+		.take_while(|(a, b)| a == b)
+		.count()
+}
+
+fn common_suffix_len<T: std::cmp::PartialEq>(list_a: &[T], list_b: &[T]) -> usize {
+	list_a
+		.iter()
+		.rev()
+		.zip(list_b.iter().rev())
+		.take_while(|(a, b)| a == b)
+		.count()
+}
+
+/// Stores the furthest reaching `x` position for each diagonal `k` in the
+/// range `-max_d - 1..=max_d + 1`.
+struct Diagonals {
+	offset: isize,
+	values: Vec<isize>,
+}
+
+impl Diagonals {
+	fn new(max_d: usize) -> Self {
+		let offset = max_d as isize + 1;
+		Diagonals {
+			offset,
+			values: vec![0; 2 * offset as usize + 1],
+		}
+	}
+
+	fn get(&self, k: isize) -> isize {
+		self.values[(k + self.offset) as usize]
+	}
+
+	fn set(&mut self, k: isize, x: isize) {
+		self.values[(k + self.offset) as usize] = x;
+	}
+
+	/// Starting `x` position for the diagonal `k` at step `d`, before
+	/// following the snake. This extends the furthest reaching path from
+	/// the neighboring diagonals.
+	fn next_x(&self, k: isize, d: isize) -> isize {
+		if k == -d || (k != d && self.get(k - 1) < self.get(k + 1)) {
+			self.get(k + 1)
+		} else {
+			self.get(k - 1) + 1
+		}
+	}
 }
 
 #[cfg(test)]
@@ -99,7 +249,7 @@ mod test_lcs {
 		let a = &[];
 		let b = &[];
 		let out = lcs::<u32>(a, b);
-		assert!(out.len() == 0);
+		assert!(out.is_empty());
 	}
 
 	#[test]
@@ -120,12 +270,12 @@ mod test_lcs {
 		let a = &[1];
 		let b = &[2];
 		let out = lcs(a, b);
-		assert!(out.len() == 0);
+		assert!(out.is_empty());
 
 		let a = &[1, 2, 3];
 		let b = &[4, 5, 6];
 		let out = lcs(a, b);
-		assert!(out.len() == 0);
+		assert!(out.is_empty());
 	}
 
 	#[test]
@@ -179,4 +329,15 @@ mod test_lcs {
 		let out = lcs(a, b);
 		assert_eq!(out, [(1, 0), (2, 1), (4, 3), (5, 4), (7, 6), (8, 7)]);
 	}
+
+	#[test]
+	fn with_large_inputs() {
+		let a = (0..20_000).collect::<Vec<_>>();
+		let b = (0..20_000)
+			.map(|x| if x % 1000 == 999 { -x } else { x })
+			.collect::<Vec<_>>();
+		let out = lcs(&a, &b);
+		assert_eq!(out.len(), 20_000 - 20);
+		assert!(out.iter().all(|&(a, b)| a == b && a % 1000 != 999));
+	}
 }
@@ -10,13 +10,13 @@
 /// must be considered in order when using the diff result:
 ///
 /// - [`Diff::Output`]: the next `count` items from both lists are the same,
-/// that is, the sequence from `source` has not been changed in the `result`.
+///   that is, the sequence from `source` has not been changed in the `result`.
 ///
 /// - [`Diff::Delete`]: the next `count` items from `source` have been removed
-/// and don't appear in the `result`.
+///   and don't appear in the `result`.
 ///
 /// - [`Diff::Insert`]: the next `count` items from `result` have been added
-/// to the current position in `source`.
+///   to the current position in `source`.
 ///
 /// Note that for a position that has both deleted and inserted items, the
 /// result will always have the [`Diff::Delete`] before the [`Diff::Insert`].
@@ -37,6 +37,9 @@ pub enum Diff {
 
 /// Computes the difference between two lists containing lines of text.
 ///
+/// This is equivalent to [`slices`](super::slices), but restricted to
+/// text lines.
+///
 /// # Example
 ///
 /// ```
@@ -62,9 +65,14 @@ pub fn lines<'a, T>(source: &'a [T], result: &'a [T]) -> DiffResult<'a, T>
 where
 	T: AsRef<str> + std::cmp::PartialEq,
 {
-	let full_source = source;
-	let full_result = result;
+	super::slices(source, result)
+}
 
+/// Computes the sequence of [`Diff`] elements between `source` and `result`.
+pub(super) fn diff_items<T>(source: &[T], result: &[T]) -> Vec<Diff>
+where
+	T: std::cmp::PartialEq,
+{
 	let common_prefix_len = {
 		let mut len = 0;
 		while len < source.len() && len < result.len() && source[len] == result[len] {
@@ -76,13 +84,9 @@ where
 	let source = &source[common_prefix_len..];
 	let result = &result[common_prefix_len..];
 
-	let both_are_equal = source.len() == 0 && result.len() == 0;
+	let both_are_equal = source.is_empty() && result.is_empty();
 	if both_are_equal {
-		return DiffResult {
-			items: Vec::new(),
-			source: full_source,
-			result: full_result,
-		};
+		return Vec::new();
 	}
 
 	let common_suffix_len = {
@@ -145,11 +149,7 @@ where
 		diff.push(Diff::Output(common_suffix_len));
 	}
 
-	DiffResult {
-		items: diff,
-		source: full_source,
-		result: full_result,
-	}
+	diff
 }
 
 /// Result of a diff operation between a `source` list and a `result` list.
@@ -167,7 +167,8 @@ where
 /// representation of the diff by implementing [`std::fmt::Display`].
 ///
 /// This requires that the `source`/`result` items implement the [`Display`](std::fmt::Display)
-/// trait.
+/// trait. For other types, use [`DiffResult::display_with`] to provide a
+/// custom formatter for the items.
 pub struct DiffResult<'a, T> {
 	items: Vec<Diff>,
 	source: &'a [T],
@@ -175,13 +176,56 @@ pub struct DiffResult<'a, T> {
 }
 
 impl<'a, T> DiffResult<'a, T> {
+	pub(super) fn new(items: Vec<Diff>, source: &'a [T], result: &'a [T]) -> Self {
+		DiffResult {
+			items,
+			source,
+			result,
+		}
+	}
+
 	pub fn is_empty(&self) -> bool {
-		self.items.len() == 0
+		self.items.is_empty()
 	}
 
 	pub fn items(&self) -> &Vec<Diff> {
 		&self.items
 	}
+
+	/// The `source` list that the diff was computed from.
+	pub fn source(&self) -> &'a [T] {
+		self.source
+	}
+
+	/// The `result` list that the diff was computed from.
+	pub fn result(&self) -> &'a [T] {
+		self.result
+	}
+
+	/// Returns a [`DiffDisplay`] that outputs the diff using `formatter` to
+	/// convert each item to text.
+	///
+	/// # Example
+	///
+	/// ```
+	/// use tux::diff;
+	///
+	/// let source = [(1, "a"), (2, "b")];
+	/// let result = [(1, "a"), (3, "c")];
+	///
+	/// let diff = diff::slices(&source, &result);
+	/// let output = diff.display_with(|(num, name)| format!("{}={}", num, name));
+	/// assert_eq!(output.to_string(), " 1=a\n-2=b\n+3=c");
+	/// ```
+	pub fn display_with<'b, F>(&'b self, formatter: F) -> DiffDisplay<'b, 'a, T>
+	where
+		F: Fn(&T) -> String + 'b,
+	{
+		DiffDisplay {
+			diff: self,
+			formatter: Box::new(formatter),
+		}
+	}
 }
 
 impl<'a, T> std::fmt::Display for DiffResult<'a, T>
@@ -189,13 +233,27 @@ where
 	T: std::fmt::Display,
 {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		let source = self.source;
-		let result = self.result;
+		self.display_with(|x| x.to_string()).fmt(f)
+	}
+}
+
+/// Outputs the full text of a [`DiffResult`] using a custom formatter for the
+/// items. See [`DiffResult::display_with`].
+pub struct DiffDisplay<'b, 'a, T> {
+	diff: &'b DiffResult<'a, T>,
+	formatter: Box<dyn Fn(&T) -> String + 'b>,
+}
+
+impl<'b, 'a, T> std::fmt::Display for DiffDisplay<'b, 'a, T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let source = self.diff.source;
+		let result = self.diff.result;
+		let format = &self.formatter;
 
 		let mut has_some_output = false;
 		let mut start_new_line = |f: &mut std::fmt::Formatter| -> std::fmt::Result {
 			if has_some_output {
-				write!(f, "\n")
+				writeln!(f)
 			} else {
 				has_some_output = true;
 				Ok(())
@@ -204,34 +262,34 @@ where
 
 		let mut cur_offset_source = 0;
 		let mut cur_offset_result = 0;
-		for item in &self.items {
+		for item in &self.diff.items {
 			match item {
 				Diff::Output(count) => {
 					let sta = cur_offset_source;
 					let end = sta + count;
 					cur_offset_source += count;
 					cur_offset_result += count;
-					for x in sta..end {
+					for line in &source[sta..end] {
 						start_new_line(f)?;
-						write!(f, " {}", source[x])?;
+						write!(f, " {}", format(line))?;
 					}
 				}
 				Diff::Insert(count) => {
 					let sta = cur_offset_result;
 					let end = sta + count;
 					cur_offset_result += count;
-					for x in sta..end {
+					for line in &result[sta..end] {
 						start_new_line(f)?;
-						write!(f, "+{}", result[x])?;
+						write!(f, "+{}", format(line))?;
 					}
 				}
 				Diff::Delete(count) => {
 					let sta = cur_offset_source;
 					let end = sta + count;
 					cur_offset_source += count;
-					for x in sta..end {
+					for line in &source[sta..end] {
 						start_new_line(f)?;
-						write!(f, "-{}", source[x])?;
+						write!(f, "-{}", format(line))?;
 					}
 				}
 			}
@@ -434,7 +492,7 @@ mod test_lines {
 
 		pub fn get_diff_text(a: Vec<&str>, b: Vec<&str>) -> String {
 			let diff = lines(&a, &b);
-			sanity_check_diff(&diff.items());
+			sanity_check_diff(diff.items());
 			diff.to_string()
 		}
 
@@ -5,5 +5,17 @@
 mod lines;
 pub use lines::*;
 
+mod color;
+pub use color::*;
+
+mod inline;
+pub use inline::*;
+
+mod slices;
+pub use slices::*;
+
+mod unified;
+pub use unified::*;
+
 mod lcs;
 use lcs::*;
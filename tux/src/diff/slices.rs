@@ -0,0 +1,116 @@
+use super::DiffResult;
+
+/// Computes the difference between two slices of arbitrary items.
+///
+/// Items are compared using [`PartialEq`]. To compare items by a derived key,
+/// use [`slices_by_key`].
+///
+/// The [`Display`](std::fmt::Display) output of the result requires that the
+/// items implement [`Display`](std::fmt::Display). For other types, use
+/// [`DiffResult::display_with`] or [`DiffResult::unified_with`] to provide
+/// a formatter.
+///
+/// # Example
+///
+/// ```
+/// use tux::diff::{self, Diff};
+///
+/// let source = [1, 2, 3, 4];
+/// let result = [1, 3, 4, 5];
+///
+/// let diff = diff::slices(&source, &result);
+/// assert_eq!(diff.to_string(), " 1\n-2\n 3\n 4\n+5");
+/// assert!(matches!(diff.items()[1], Diff::Delete(1)));
+/// ```
+pub fn slices<'a, T>(source: &'a [T], result: &'a [T]) -> DiffResult<'a, T>
+where
+	T: std::cmp::PartialEq,
+{
+	let items = super::diff_items(source, result);
+	DiffResult::new(items, source, result)
+}
+
+/// Computes the difference between two slices, comparing the items by the
+/// key returned from the `key` function.
+///
+/// This is useful for items that don't implement [`PartialEq`], or to ignore
+/// some of the item data in the comparison. Note that for items with the
+/// same key, the output always uses the item from `source`.
+///
+/// # Example
+///
+/// ```
+/// use tux::diff;
+///
+/// struct Token {
+///     text: &'static str,
+///     offset: usize,
+/// }
+///
+/// let source = [Token { text: "a", offset: 0 }, Token { text: "b", offset: 1 }];
+/// let result = [Token { text: "a", offset: 5 }, Token { text: "c", offset: 6 }];
+///
+/// let diff = diff::slices_by_key(&source, &result, |x| x.text);
+/// let output = diff.display_with(|x| x.text.to_string());
+/// assert_eq!(output.to_string(), " a\n-b\n+c");
+/// ```
+pub fn slices_by_key<'a, T, K, F>(source: &'a [T], result: &'a [T], mut key: F) -> DiffResult<'a, T>
+where
+	K: std::cmp::PartialEq,
+	F: FnMut(&T) -> K,
+{
+	let source_keys = source.iter().map(&mut key).collect::<Vec<_>>();
+	let result_keys = result.iter().map(&mut key).collect::<Vec<_>>();
+	let items = super::diff_items(&source_keys, &result_keys);
+	DiffResult::new(items, source, result)
+}
+
+#[cfg(test)]
+mod test_slices {
+	use super::{slices, slices_by_key};
+	use crate::text;
+
+	#[test]
+	fn of_empty() {
+		let diff = slices::<u8>(&[], &[]);
+		assert!(diff.is_empty());
+	}
+
+	#[test]
+	fn of_non_text_items() {
+		let a = [1, 2, 3];
+		let b = [0, 2, 3, 4];
+		let diff = slices(&a, &b);
+		assert_eq!(
+			diff.to_string(),
+			text::join_lines(["-1", "+0", " 2", " 3", "+4"])
+		);
+	}
+
+	#[test]
+	fn with_custom_formatter() {
+		#[derive(PartialEq)]
+		struct Item(u32);
+
+		let a = [Item(1), Item(2)];
+		let b = [Item(1), Item(3)];
+		let diff = slices(&a, &b);
+		let out = diff.display_with(|x| format!("Item({})", x.0)).to_string();
+		assert_eq!(out, text::join_lines([" Item(1)", "-Item(2)", "+Item(3)"]));
+
+		let out = diff.unified_with(|x| format!("#{}", x.0)).to_string();
+		assert_eq!(
+			out,
+			text::join_lines(["@@ -1,2 +1,2 @@", " #1", "-#2", "+#3"])
+		);
+	}
+
+	#[test]
+	fn by_key() {
+		let a = [("a", 1), ("b", 2), ("c", 3)];
+		let b = [("a", 10), ("c", 30), ("d", 40)];
+		let diff = slices_by_key(&a, &b, |x| x.0);
+		let out = diff.display_with(|x| format!("{}{}", x.0, x.1)).to_string();
+		assert_eq!(out, text::join_lines([" a1", "-b2", " c3", "+d40"]));
+	}
+}
@@ -0,0 +1,498 @@
+use super::color::{paint, BOLD, CYAN, DIM, GREEN, NO_REVERSE, RED, REVERSE};
+use super::{Diff, DiffResult, InlineMode};
+
+/// Default number of context lines around each change in a [`UnifiedDiff`].
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// Renders a [`DiffResult`] in the unified diff format, as generated by
+/// `diff -u` and `git diff`.
+///
+/// Only the changed lines and a configurable number of context lines around
+/// them are output, grouped in hunks with a `@@ -a,b +c,d @@` header.
+///
+/// Use [`DiffResult::unified`] to create an instance.
+///
+/// # Example
+///
+/// ```
+/// use tux::diff;
+///
+/// let source = vec!["1", "2", "3", "4", "5", "6"];
+/// let result = vec!["1", "2", "3", "4", "X", "6"];
+///
+/// let diff = diff::lines(&source, &result);
+/// let output = diff.unified().context(1).labels("a.txt", "b.txt");
+/// println!("{}", output);
+/// ```
+///
+/// This will output:
+///
+/// ```text
+/// --- a.txt
+/// +++ b.txt
+/// @@ -4,3 +4,3 @@
+///  4
+/// -5
+/// +X
+///  6
+/// ```
+pub struct UnifiedDiff<'b, 'a, T> {
+	diff: &'b DiffResult<'a, T>,
+	context: usize,
+	labels: Option<(String, String)>,
+	inline: Option<InlineMode>,
+	color: bool,
+	formatter: Box<dyn Fn(&T) -> String + 'b>,
+}
+
+impl<'a, T: std::fmt::Display> DiffResult<'a, T> {
+	/// Returns a [`UnifiedDiff`] that renders this result in the unified diff
+	/// format, with [`DEFAULT_CONTEXT_LINES`] lines of context.
+	pub fn unified(&self) -> UnifiedDiff<'_, 'a, T> {
+		self.unified_with(|x| x.to_string())
+	}
+}
+
+impl<'a, T> DiffResult<'a, T> {
+	/// Same as [`unified`](Self::unified), but uses `formatter` to convert
+	/// each item to text.
+	pub fn unified_with<'b, F>(&'b self, formatter: F) -> UnifiedDiff<'b, 'a, T>
+	where
+		F: Fn(&T) -> String + 'b,
+	{
+		UnifiedDiff {
+			diff: self,
+			context: DEFAULT_CONTEXT_LINES,
+			labels: None,
+			inline: None,
+			color: false,
+			formatter: Box::new(formatter),
+		}
+	}
+}
+
+impl<'b, 'a, T> UnifiedDiff<'b, 'a, T> {
+	/// Sets the number of unchanged lines to output around each change.
+	pub fn context(mut self, lines: usize) -> Self {
+		self.context = lines;
+		self
+	}
+
+	/// Sets the file labels for the `source` and `result`. Those are output
+	/// as the `---` and `+++` header lines.
+	///
+	/// If not set, the header lines are not output.
+	pub fn labels<S1: Into<String>, S2: Into<String>>(mut self, source: S1, result: S2) -> Self {
+		self.labels = Some((source.into(), result.into()));
+		self
+	}
+
+	/// Enables intra-line highlighting of changed lines. When enabled, each
+	/// removed line is paired with the respective inserted line, and the
+	/// changes between them are marked (see [`InlineDiff`](super::InlineDiff)).
+	///
+	/// Lines without anything in common are not marked.
+	pub fn inline(mut self, mode: InlineMode) -> Self {
+		self.inline = Some(mode);
+		self
+	}
+
+	/// Enables ANSI colors in the output: removed lines are red, inserted
+	/// lines are green, and context lines are dimmed.
+	///
+	/// With colors enabled, the [`inline`](Self::inline) highlighting uses
+	/// reverse video instead of text markers.
+	///
+	/// Use [`use_color_for_stderr`](super::use_color_for_stderr) to decide
+	/// based on the environment.
+	pub fn color(mut self, enabled: bool) -> Self {
+		self.color = enabled;
+		self
+	}
+
+	/// Applies the ANSI style to the text, if colors are enabled.
+	fn style(&self, style: &str, text: String) -> String {
+		if self.color {
+			paint(style, &text)
+		} else {
+			text
+		}
+	}
+
+	/// Returns the hunks of the diff, considering the current context size.
+	pub(crate) fn hunks(&self) -> Vec<Hunk> {
+		let context = self.context;
+		let source_len = self.diff.source().len();
+		let result_len = self.diff.result().len();
+
+		let mut hunks: Vec<Hunk> = Vec::new();
+		let mut cur_source = 0;
+		let mut cur_result = 0;
+		for item in self.diff.items() {
+			let change = match item {
+				Diff::Output(count) => {
+					cur_source += count;
+					cur_result += count;
+					continue;
+				}
+				Diff::Delete(count) => {
+					cur_source += count;
+					Change {
+						source: cur_source - count..cur_source,
+						result: cur_result..cur_result,
+					}
+				}
+				Diff::Insert(count) => {
+					cur_result += count;
+					Change {
+						source: cur_source..cur_source,
+						result: cur_result - count..cur_result,
+					}
+				}
+			};
+
+			// changes are merged into the previous hunk if they are close
+			// enough that their context lines would overlap or touch
+			if let Some(hunk) = hunks.last_mut() {
+				let last = hunk.changes.last_mut().unwrap();
+				if last.source.end == change.source.start && last.result.end == change.result.start
+				{
+					last.source.end = change.source.end;
+					last.result.end = change.result.end;
+					continue;
+				} else if change.source.start - last.source.end <= 2 * context {
+					hunk.changes.push(change);
+					continue;
+				}
+			}
+
+			hunks.push(Hunk {
+				changes: vec![change],
+				source: 0..0,
+				result: 0..0,
+			});
+		}
+
+		for hunk in hunks.iter_mut() {
+			let first = hunk.changes.first().unwrap();
+			let last = hunk.changes.last().unwrap();
+			let before = std::cmp::min(context, first.source.start);
+			let after = std::cmp::min(context, source_len - last.source.end);
+			let after = std::cmp::min(after, result_len - last.result.end);
+			hunk.source = first.source.start - before..last.source.end + after;
+			hunk.result = first.result.start - before..last.result.end + after;
+		}
+
+		hunks
+	}
+}
+
+/// A group of nearby changes in a unified diff, along with the range of
+/// lines it covers in the `source` and `result`, including context.
+pub(crate) struct Hunk {
+	pub source: std::ops::Range<usize>,
+	pub result: std::ops::Range<usize>,
+	pub changes: Vec<Change>,
+}
+
+/// A contiguous sequence of deleted and inserted lines.
+pub(crate) struct Change {
+	pub source: std::ops::Range<usize>,
+	pub result: std::ops::Range<usize>,
+}
+
+impl Hunk {
+	/// The hunk header in the `@@ -a,b +c,d @@` format.
+	pub fn header(&self) -> String {
+		format!(
+			"@@ -{} +{} @@",
+			format_range(&self.source),
+			format_range(&self.result)
+		)
+	}
+}
+
+/// Formats a line range for a hunk header using 1-based line numbers. Empty
+/// ranges use the line number before the range, as in `diff -u`.
+fn format_range(range: &std::ops::Range<usize>) -> String {
+	match range.len() {
+		0 => format!("{},0", range.start),
+		1 => format!("{}", range.start + 1),
+		len => format!("{},{}", range.start + 1, len),
+	}
+}
+
+impl<'b, 'a, T> std::fmt::Display for UnifiedDiff<'b, 'a, T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.diff.is_empty() {
+			return Ok(());
+		}
+
+		let format = &self.formatter;
+		let source = self.diff.source();
+		let result = self.diff.result();
+
+		let mut lines = Vec::new();
+		if let Some((source_label, result_label)) = &self.labels {
+			lines.push(self.style(BOLD, format!("--- {}", source_label)));
+			lines.push(self.style(BOLD, format!("+++ {}", result_label)));
+		}
+
+		for hunk in self.hunks() {
+			lines.push(self.style(CYAN, hunk.header()));
+
+			let mut cur_source = hunk.source.start;
+			for change in hunk.changes.iter() {
+				for line in &source[cur_source..change.source.start] {
+					lines.push(self.style(DIM, format!(" {}", format(line))));
+				}
+				let removed = source[change.source.clone()].iter().map(format);
+				let inserted = result[change.result.clone()].iter().map(format);
+				let mut removed = removed.collect::<Vec<_>>();
+				let mut inserted = inserted.collect::<Vec<_>>();
+				if let Some(mode) = self.inline {
+					for (old, new) in removed.iter_mut().zip(inserted.iter_mut()) {
+						let diff = super::inline(old, new, mode);
+						if !diff.has_common_tokens() {
+							continue;
+						}
+						let (marked_old, marked_new) = if self.color {
+							let highlight =
+								|text: &str| format!("{}{}{}", REVERSE, text, NO_REVERSE);
+							let marked_old = diff.render(highlight, |_| String::new());
+							let marked_new = diff.render(|_| String::new(), highlight);
+							(marked_old, marked_new)
+						} else {
+							(diff.source_marked(), diff.result_marked())
+						};
+						(*old, *new) = (marked_old, marked_new);
+					}
+				}
+				for line in removed {
+					lines.push(self.style(RED, format!("-{}", line)));
+				}
+				for line in inserted {
+					lines.push(self.style(GREEN, format!("+{}", line)));
+				}
+				cur_source = change.source.end;
+			}
+			for line in &source[cur_source..hunk.source.end] {
+				lines.push(self.style(DIM, format!(" {}", format(line))));
+			}
+		}
+
+		write!(f, "{}", lines.join("\n"))
+	}
+}
+
+#[cfg(test)]
+mod test_unified {
+	use super::super::{lines, InlineMode};
+	use crate::text;
+
+	#[test]
+	fn is_empty_for_equal_inputs() {
+		let a = ["line 1", "line 2"];
+		let b = ["line 1", "line 2"];
+		let diff = lines(&a, &b);
+		let out = diff.unified().labels("a", "b").to_string();
+		assert_eq!(out, "");
+	}
+
+	#[test]
+	fn outputs_labels() {
+		let a = ["same", "a"];
+		let b = ["same", "b"];
+		let diff = lines(&a, &b);
+		let out = diff.unified().labels("file a", "file b").to_string();
+		assert_eq!(
+			out,
+			text::join_lines([
+				"--- file a",
+				"+++ file b",
+				"@@ -1,2 +1,2 @@",
+				" same",
+				"-a",
+				"+b"
+			])
+		);
+	}
+
+	#[test]
+	fn outputs_context_around_changes() {
+		let a = helper::numbers(1..=20);
+		let b = helper::replace(&a, &[(10, "X")]);
+		let diff = lines(&a, &b);
+		let out = diff.unified().to_string();
+		assert_eq!(
+			out,
+			text::join_lines([
+				"@@ -7,7 +7,7 @@",
+				" 7",
+				" 8",
+				" 9",
+				"-10",
+				"+X",
+				" 11",
+				" 12",
+				" 13",
+			])
+		);
+	}
+
+	#[test]
+	fn supports_custom_context() {
+		let a = helper::numbers(1..=20);
+		let b = helper::replace(&a, &[(10, "X")]);
+		let diff = lines(&a, &b);
+
+		let out = diff.unified().context(1).to_string();
+		assert_eq!(
+			out,
+			text::join_lines(["@@ -9,3 +9,3 @@", " 9", "-10", "+X", " 11"])
+		);
+
+		let out = diff.unified().context(0).to_string();
+		assert_eq!(out, text::join_lines(["@@ -10 +10 @@", "-10", "+X"]));
+	}
+
+	#[test]
+	fn splits_distant_changes_into_hunks() {
+		let a = helper::numbers(1..=20);
+		let b = helper::replace(&a, &[(3, "A"), (17, "B")]);
+		let diff = lines(&a, &b);
+		let out = diff.unified().context(2).to_string();
+		assert_eq!(
+			out,
+			text::join_lines([
+				"@@ -1,5 +1,5 @@",
+				" 1",
+				" 2",
+				"-3",
+				"+A",
+				" 4",
+				" 5",
+				"@@ -15,5 +15,5 @@",
+				" 15",
+				" 16",
+				"-17",
+				"+B",
+				" 18",
+				" 19",
+			])
+		);
+	}
+
+	#[test]
+	fn merges_changes_with_overlapping_context() {
+		let a = helper::numbers(1..=10);
+		let b = helper::replace(&a, &[(3, "A"), (7, "B")]);
+		let diff = lines(&a, &b);
+		let out = diff.unified().context(2).to_string();
+		assert_eq!(
+			out,
+			text::join_lines([
+				"@@ -1,9 +1,9 @@",
+				" 1",
+				" 2",
+				"-3",
+				"+A",
+				" 4",
+				" 5",
+				" 6",
+				"-7",
+				"+B",
+				" 8",
+				" 9",
+			])
+		);
+	}
+
+	#[test]
+	fn outputs_insertions_and_deletions() {
+		let a = ["1", "2", "3", "4"];
+		let b = ["1", "2", "new", "3"];
+		let diff = lines(&a, &b);
+		let out = diff.unified().context(1).to_string();
+		assert_eq!(
+			out,
+			text::join_lines(["@@ -2,3 +2,3 @@", " 2", "+new", " 3", "-4"])
+		);
+	}
+
+	#[test]
+	fn uses_previous_line_for_empty_ranges() {
+		let a: [&str; 0] = [];
+		let b = ["1", "2"];
+		let diff = lines(&a, &b);
+		let out = diff.unified().to_string();
+		assert_eq!(out, text::join_lines(["@@ -0,0 +1,2 @@", "+1", "+2"]));
+
+		let a = ["1", "2", "3"];
+		let b = ["1", "3"];
+		let diff = lines(&a, &b);
+		let out = diff.unified().context(0).to_string();
+		assert_eq!(out, text::join_lines(["@@ -2 +1,0 @@", "-2"]));
+	}
+
+	#[test]
+	fn supports_inline_highlighting() {
+		let a = ["same", "let a = 1;", "let b = 2;", "removed"];
+		let b = ["same", "let a = 10;", "different", "same"];
+		let diff = lines(&a, &b);
+		let out = diff.unified().inline(InlineMode::Words).to_string();
+		assert_eq!(
+			out,
+			text::join_lines([
+				"@@ -1,4 +1,4 @@",
+				" same",
+				"-let a = [-1-];",
+				"-let b = 2;",
+				"-removed",
+				"+let a = {+10+};",
+				"+different",
+				"+same",
+			])
+		);
+	}
+
+	#[test]
+	fn supports_colors() {
+		let a = ["same", "a", "x = 1"];
+		let b = ["same", "b", "x = 2"];
+		let diff = lines(&a, &b);
+		let out = diff
+			.unified()
+			.labels("a", "b")
+			.inline(InlineMode::Words)
+			.color(true)
+			.to_string();
+		assert_eq!(
+			out,
+			text::join_lines([
+				"\x1b[1m--- a\x1b[0m",
+				"\x1b[1m+++ b\x1b[0m",
+				"\x1b[36m@@ -1,3 +1,3 @@\x1b[0m",
+				"\x1b[2m same\x1b[0m",
+				"\x1b[31m-a\x1b[0m",
+				"\x1b[31m-x = \x1b[7m1\x1b[27m\x1b[0m",
+				"\x1b[32m+b\x1b[0m",
+				"\x1b[32m+x = \x1b[7m2\x1b[27m\x1b[0m",
+			])
+		);
+	}
+
+	mod helper {
+		pub fn numbers(range: std::ops::RangeInclusive<usize>) -> Vec<String> {
+			range.map(|x| x.to_string()).collect()
+		}
+
+		pub fn replace(lines: &[String], changes: &[(usize, &str)]) -> Vec<String> {
+			let mut lines = lines.to_vec();
+			for (number, text) in changes {
+				lines[number - 1] = text.to_string();
+			}
+			lines
+		}
+	}
+}
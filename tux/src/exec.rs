@@ -47,20 +47,26 @@ pub fn run_bin(cmd: &str, args: &[&str]) -> String {
 ///
 /// This will panic if the process exit status is non-zero or if any
 /// error output is generated.
+///
+/// The redactions bound to the current thread with [`Redactions::bind`](crate::redact::Redactions::bind)
+/// are applied to the returned output.
 pub fn get_process_output(output: std::process::Output) -> String {
 	let stderr = String::from_utf8_lossy(&output.stderr);
 	if !output.status.success() {
 		panic!(
 			"executable exited with error ({}){}",
 			output.status,
-			if stderr.len() > 0 {
+			if !stderr.is_empty() {
 				format!(" and error output: {}", stderr)
 			} else {
 				"".into()
 			}
 		);
-	} else if stderr.len() > 0 {
+	} else if !stderr.is_empty() {
 		panic!("executable generated error output: {}", stderr);
 	}
-	String::from_utf8(output.stdout).expect("reading output as utf-8")
+	let stdout = String::from_utf8(output.stdout).expect("reading output as utf-8");
+	#[cfg(feature = "redact")]
+	let stdout = crate::redact::apply_current(stdout);
+	stdout
 }
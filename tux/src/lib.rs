@@ -14,10 +14,14 @@
 //!
 //! All other features are enabled by default:
 //!
-//! - `diff`: support for the text diff functions.
+//! - `diff`: support for the text diff functions and [`assert_eq_diff!`].
 //! - `exec`: support for the binary execution functions.
+//! - `redact`: support for redacting volatile values from test output, such
+//!   as temporary paths and timestamps. See [`redact`].
+//! - `snapshot`: support for snapshot tests with [`assert_snapshot!`].
 //! - `temp`: helpers for managing temporary directories and files.
-//! - `testdata`: support for file based tests.
+//! - `testdata`: support for file based tests, including the [`testdata`](macro@testdata)
+//!   attribute to generate a test for each case.
 //! - `text`: text utility functions.
 //!
 //! To disable the default features and opt into specific ones, change the
@@ -31,18 +35,30 @@
 
 pub mod assert_panic;
 
+#[cfg(feature = "diff")]
+pub mod assert_eq_diff;
+
 #[cfg(feature = "exec")]
 mod exec;
 
 #[cfg(feature = "exec")]
 pub use exec::*;
 
+#[cfg(feature = "redact")]
+pub mod redact;
+
 #[cfg(feature = "server")]
 mod server;
 
 #[cfg(feature = "server")]
 pub use server::*;
 
+#[cfg(feature = "snapshot")]
+mod snapshot;
+
+#[cfg(feature = "snapshot")]
+pub use snapshot::*;
+
 #[cfg(feature = "temp")]
 mod temp;
 
@@ -55,6 +71,9 @@ mod testdata;
 #[cfg(feature = "testdata")]
 pub use testdata::*;
 
+#[cfg(feature = "testdata")]
+pub use tux_macros::testdata;
+
 #[cfg(feature = "text")]
 pub mod text;
 
@@ -0,0 +1,379 @@
+//! Support for redacting volatile values from test output, such as temporary
+//! paths, timestamps, durations, process ids, and other ids.
+//!
+//! This module is enabled by the `redact` feature (enabled by default).
+//!
+//! A [`Redactions`] value is a list of named rules, each one replacing the
+//! matches of a regular expression with a placeholder:
+//!
+//! ```
+//! use tux::redact::Redactions;
+//!
+//! let redactions = Redactions::builtin().rule("port", r"port \d+", "port [PORT]");
+//! let output = redactions.apply("started pid 1234 on port 8080 in 12ms");
+//! assert_eq!(output, "started pid [PID] on port [PORT] in [DURATION]");
+//! ```
+//!
+//! Redactions can be applied explicitly, configured for a [`TestData`](crate::TestData)
+//! run, or bound to the current thread with [`Redactions::bind`]. Bound
+//! redactions are applied to the [`testdata`](fn@crate::testdata) output,
+//! snapshots, and the output of [`run_bin`](crate::run_bin) before comparing,
+//! which keeps the expected files stable across machines.
+
+use std::{cell::RefCell, path::Path};
+
+use regex::Regex;
+
+/// Placeholder for the temporary directories, including those created by
+/// [`TempDir`](crate::TempDir).
+pub const TEMP_DIR_PLACEHOLDER: &str = "[TEMP]";
+
+/// Placeholder for the current directory.
+pub const CURRENT_DIR_PLACEHOLDER: &str = "[CWD]";
+
+/// Placeholder for date and time values.
+pub const TIMESTAMP_PLACEHOLDER: &str = "[TIMESTAMP]";
+
+/// Placeholder for durations (e.g. `12ms` or `1.5s`).
+pub const DURATION_PLACEHOLDER: &str = "[DURATION]";
+
+/// Placeholder for UUIDs.
+pub const UUID_PLACEHOLDER: &str = "[UUID]";
+
+/// Placeholder for hexadecimal ids, such as hashes and addresses.
+pub const HEX_ID_PLACEHOLDER: &str = "[HEX]";
+
+/// Placeholder for process ids.
+pub const PID_PLACEHOLDER: &str = "[PID]";
+
+thread_local! {
+	static CURRENT: RefCell<Option<Redactions>> = const { RefCell::new(None) };
+}
+
+/// List of named redaction rules, applied in order.
+///
+/// Adding a rule with the same name as an existing rule replaces it, which
+/// allows customizing the built-in rules.
+#[derive(Clone, Debug, Default)]
+pub struct Redactions {
+	rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+	name: String,
+	regex: Regex,
+	replacement: String,
+	// built-in rules can skip some of the matches
+	filter: Option<fn(&str) -> bool>,
+}
+
+impl Redactions {
+	/// Creates an empty list of redactions.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a list with all the built-in rules: [`paths`](Self::paths),
+	/// [`timestamps`](Self::timestamps), [`durations`](Self::durations),
+	/// [`hex_ids`](Self::hex_ids), and [`pids`](Self::pids).
+	pub fn builtin() -> Self {
+		Self::new()
+			.paths()
+			.timestamps()
+			.durations()
+			.hex_ids()
+			.pids()
+	}
+
+	/// Adds a named rule replacing all matches of the regular expression.
+	///
+	/// The replacement can reference capture groups (e.g. `$1` or `${name}`),
+	/// as in [`Regex::replace_all`].
+	///
+	/// # Panics
+	///
+	/// Panics if the regular expression is invalid.
+	pub fn rule<S1, S2>(self, name: S1, pattern: &str, replacement: S2) -> Self
+	where
+		S1: Into<String>,
+		S2: Into<String>,
+	{
+		let name = name.into();
+		let regex = Regex::new(pattern)
+			.unwrap_or_else(|err| panic!("invalid pattern for redaction `{}`: {}", name, err));
+		self.with_rule(Rule {
+			name,
+			regex,
+			replacement: replacement.into(),
+			filter: None,
+		})
+	}
+
+	/// Removes the rule with the given name, if any.
+	pub fn without(mut self, name: &str) -> Self {
+		self.rules.retain(|x| x.name != name);
+		self
+	}
+
+	/// Adds the `temp_dir` and `current_dir` rules, which replace the system
+	/// temporary directory with [`TEMP_DIR_PLACEHOLDER`] and the current
+	/// directory with [`CURRENT_DIR_PLACEHOLDER`].
+	///
+	/// The random directory names created by [`TempDir`](crate::TempDir) are
+	/// part of the redacted path, so `/tmp/.tmpA1b2C3/file` becomes
+	/// `[TEMP]/file`.
+	pub fn paths(self) -> Self {
+		let temp_dir = std::env::temp_dir();
+		let temp_dir = path_pattern(&temp_dir);
+		let redactions = self.rule(
+			"temp_dir",
+			&format!(r"{}(?:[/\\]\.tmp[0-9A-Za-z]+)?", temp_dir),
+			TEMP_DIR_PLACEHOLDER,
+		);
+		match std::env::current_dir() {
+			Ok(current_dir) => redactions.rule(
+				"current_dir",
+				&path_pattern(&current_dir),
+				CURRENT_DIR_PLACEHOLDER,
+			),
+			Err(_) => redactions,
+		}
+	}
+
+	/// Adds the `timestamp` rule, which replaces ISO 8601 date and times
+	/// (e.g. `2024-01-02T10:20:30.123Z` or `2024-01-02 10:20:30`) with
+	/// [`TIMESTAMP_PLACEHOLDER`].
+	pub fn timestamps(self) -> Self {
+		self.rule(
+			"timestamp",
+			r"\b\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(?::\d{2}(?:[.,]\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?",
+			TIMESTAMP_PLACEHOLDER,
+		)
+	}
+
+	/// Adds the `duration` rule, which replaces durations with a time unit
+	/// (e.g. `150ns`, `12ms`, `1.5s`) with [`DURATION_PLACEHOLDER`].
+	pub fn durations(self) -> Self {
+		self.rule(
+			"duration",
+			r"\b\d+(?:\.\d+)?(?:ns|us|µs|ms|s)\b",
+			DURATION_PLACEHOLDER,
+		)
+	}
+
+	/// Adds the `uuid` and `hex_id` rules, which replace UUIDs with
+	/// [`UUID_PLACEHOLDER`] and hexadecimal values with at least 8 digits
+	/// (e.g. hashes and addresses) with [`HEX_ID_PLACEHOLDER`].
+	///
+	/// To avoid redacting plain numbers and words, hexadecimal values must
+	/// contain both digits and letters, unless prefixed with `0x`.
+	pub fn hex_ids(self) -> Self {
+		let redactions = self.rule(
+			"uuid",
+			r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+			UUID_PLACEHOLDER,
+		);
+		redactions.with_rule(Rule {
+			name: "hex_id".into(),
+			regex: Regex::new(r"\b(?:0x[0-9a-fA-F]+|[0-9a-fA-F]{8,})\b").unwrap(),
+			replacement: HEX_ID_PLACEHOLDER.into(),
+			filter: Some(|text| {
+				text.starts_with("0x")
+					|| (text.contains(|c: char| c.is_ascii_digit())
+						&& text.contains(|c: char| c.is_ascii_alphabetic()))
+			}),
+		})
+	}
+
+	/// Adds the `pid` rule, which replaces process ids following a `pid` label
+	/// (e.g. `pid 1234`, `PID: 1234`, or `pid=1234`) with [`PID_PLACEHOLDER`].
+	///
+	/// Process ids without a label cannot be told apart from other numbers,
+	/// so these need a custom rule (see [`rule`](Self::rule)).
+	pub fn pids(self) -> Self {
+		self.rule(
+			"pid",
+			r"(?i)\b(pid\s*[=:]?\s*)\d+\b",
+			format!("${{1}}{}", PID_PLACEHOLDER),
+		)
+	}
+
+	/// Returns the names of the rules, in the order they are applied.
+	pub fn names(&self) -> Vec<&str> {
+		self.rules.iter().map(|x| x.name.as_str()).collect()
+	}
+
+	/// Returns the text with all rules applied, in order.
+	pub fn apply(&self, text: &str) -> String {
+		let mut text = text.to_string();
+		for rule in self.rules.iter() {
+			let replaced = match rule.filter {
+				None => rule.regex.replace_all(&text, rule.replacement.as_str()),
+				Some(filter) => rule.regex.replace_all(&text, |caps: &regex::Captures| {
+					let matched = &caps[0];
+					if filter(matched) {
+						rule.replacement.clone()
+					} else {
+						matched.to_string()
+					}
+				}),
+			};
+			text = replaced.into_owned();
+		}
+		text
+	}
+
+	/// Binds the redactions to the current thread while running the given
+	/// function. Bound redactions are applied to the [`testdata`](fn@crate::testdata)
+	/// output, snapshots, and the output of [`run_bin`](crate::run_bin).
+	///
+	/// ```
+	/// use tux::redact::{self, Redactions};
+	///
+	/// let redactions = Redactions::new().rule("pid", r"pid \d+", "pid [PID]");
+	/// let output = redactions.bind(|| redact::apply_current("pid 1234".to_string()));
+	/// assert_eq!(output, "pid [PID]");
+	/// ```
+	///
+	/// The previous redactions are restored once the function returns.
+	pub fn bind<T, F: FnOnce() -> T>(&self, f: F) -> T {
+		let _guard = set_current(Some(self.clone()));
+		f()
+	}
+
+	fn with_rule(mut self, rule: Rule) -> Self {
+		match self.rules.iter_mut().find(|x| x.name == rule.name) {
+			Some(existing) => *existing = rule,
+			None => self.rules.push(rule),
+		}
+		self
+	}
+}
+
+/// Returns the redactions bound to the current thread, if any.
+pub fn current() -> Option<Redactions> {
+	CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Applies the redactions bound to the current thread to the text, which
+/// is returned as is if there are none.
+///
+/// This can be used to support bound redactions in custom assertions.
+pub fn apply_current(text: String) -> String {
+	CURRENT.with(|current| match current.borrow().as_ref() {
+		Some(redactions) => redactions.apply(&text),
+		None => text,
+	})
+}
+
+/// Sets the redactions for the current thread, until the returned guard is
+/// dropped.
+pub(crate) fn set_current(redactions: Option<Redactions>) -> CurrentGuard {
+	let previous = CURRENT.with(|current| current.replace(redactions));
+	CurrentGuard { previous }
+}
+
+/// Restores the previous redactions for the thread on drop.
+pub(crate) struct CurrentGuard {
+	previous: Option<Redactions>,
+}
+
+impl Drop for CurrentGuard {
+	fn drop(&mut self) {
+		let previous = self.previous.take();
+		CURRENT.with(|current| current.replace(previous));
+	}
+}
+
+/// Returns the pattern for a path, matching either path separator.
+fn path_pattern(path: &Path) -> String {
+	let path = path.to_string_lossy();
+	let path = path.trim_end_matches(['/', '\\']);
+	path.split(['/', '\\'])
+		.map(regex::escape)
+		.collect::<Vec<_>>()
+		.join(r"[/\\]")
+}
+
+#[cfg(test)]
+mod test_redact {
+	use super::Redactions;
+
+	#[test]
+	fn applies_named_rules_in_order() {
+		let redactions = Redactions::new().rule("pid", r"pid=\d+", "pid=[PID]").rule(
+			"port",
+			r":(\d{4,5})\b",
+			":[PORT]",
+		);
+		let output = redactions.apply("server pid=123 at 127.0.0.1:8080");
+		assert_eq!(output, "server pid=[PID] at 127.0.0.1:[PORT]");
+		assert_eq!(redactions.names(), ["pid", "port"]);
+	}
+
+	#[test]
+	fn rule_with_same_name_replaces_existing() {
+		let redactions = Redactions::builtin().rule("duration", r"\d+ms", "[MS]");
+		assert_eq!(redactions.apply("took 12ms"), "took [MS]");
+		assert_eq!(redactions.apply("took 1s"), "took 1s");
+
+		let redactions = redactions.without("duration");
+		assert_eq!(redactions.apply("took 12ms"), "took 12ms");
+	}
+
+	#[test]
+	fn redacts_temp_dirs() {
+		let dir = std::env::temp_dir().join(".tmpA1b2C3").join("file.txt");
+		let output = Redactions::new().paths().apply(&dir.to_string_lossy());
+		assert!(output.starts_with("[TEMP]"), "output: {}", output);
+		assert!(output.ends_with("file.txt"));
+		assert!(!output.contains("tmpA1b2C3"));
+	}
+
+	#[test]
+	fn redacts_current_dir() {
+		let dir = std::env::current_dir().unwrap().join("src");
+		let output = Redactions::new().paths().apply(&dir.to_string_lossy());
+		assert_eq!(output, format!("[CWD]{}src", std::path::MAIN_SEPARATOR));
+	}
+
+	#[test]
+	fn redacts_timestamps_and_durations() {
+		let redactions = Redactions::builtin();
+		let output = redactions.apply("at 2024-01-02T10:20:30.123Z took 1.5s");
+		assert_eq!(output, "at [TIMESTAMP] took [DURATION]");
+		let output = redactions.apply("at 2024-01-02 10:20:30+02:00 took 150µs");
+		assert_eq!(output, "at [TIMESTAMP] took [DURATION]");
+		assert_eq!(redactions.apply("3 items"), "3 items");
+	}
+
+	#[test]
+	fn redacts_hex_ids() {
+		let redactions = Redactions::builtin();
+		let output = redactions.apply("id 123e4567-e89b-12d3-a456-426614174000");
+		assert_eq!(output, "id [UUID]");
+		let output = redactions.apply("commit 0a1b2c3d4e5f at 0x7ffd");
+		assert_eq!(output, "commit [HEX] at [HEX]");
+		let output = redactions.apply("count 12345678 of deadbeefcafe");
+		assert_eq!(output, "count 12345678 of deadbeefcafe");
+	}
+
+	#[test]
+	fn redacts_pids() {
+		let redactions = Redactions::builtin();
+		let output = redactions.apply("pid 1234, PID: 42, pid=7");
+		assert_eq!(output, "pid [PID], PID: [PID], pid=[PID]");
+		assert_eq!(redactions.apply("rapid 12"), "rapid 12");
+	}
+
+	#[test]
+	fn bind_sets_current_redactions() {
+		assert!(super::current().is_none());
+		let output = Redactions::new()
+			.rule("x", "x", "y")
+			.bind(|| super::apply_current("x".into()));
+		assert_eq!(output, "y");
+		assert!(super::current().is_none());
+	}
+}
@@ -0,0 +1,290 @@
+//! Support for snapshot tests.
+//!
+//! This module is enabled by the `snapshot` feature (enabled by default).
+
+use std::{
+	io::ErrorKind,
+	path::{Path, PathBuf},
+};
+
+const SNAPSHOT_DIR: &str = "snapshots";
+const SNAPSHOT_FILE_EXTENSION: &str = "snap";
+const SNAPSHOT_NEW_FILE_EXTENSION: &str = "snap.new";
+
+/// Environment variable that enables the update mode for snapshots.
+pub const SNAPSHOT_UPDATE_VAR: &str = "TUX_UPDATE";
+
+/// Asserts that a value matches a snapshot of its expected text output.
+///
+/// # File snapshots
+///
+/// The `assert_snapshot!(name, value)` form compares the value formatted
+/// with [`Display`](std::fmt::Display) to the snapshot stored in the
+/// `snapshots/{name}.snap` file, in the same directory as the source file
+/// calling the macro.
+///
+/// ```no_run
+/// # use tux::assert_snapshot;
+/// let output = format!("{:#?}", vec![1, 2, 3]);
+/// assert_snapshot!("list_output", output);
+/// ```
+///
+/// Both the value and the snapshot are normalized with [`text::lines`](fn@crate::text::lines)
+/// before comparing. On a mismatch, the macro panics with a diff between the
+/// value and the snapshot.
+///
+/// If the snapshot file does not exist, the assertion fails and the value is
+/// saved to a `.snap.new` file, which can be renamed to `.snap` after being
+/// inspected. This is the same as `.valid.new` files for `testdata`.
+///
+/// ## Update mode
+///
+/// If the `TUX_UPDATE` environment variable is set to `1`, the assertion will
+/// not fail. Instead, missing or mismatched snapshot files are written with
+/// the new value.
+///
+/// ```text
+/// TUX_UPDATE=1 cargo test
+/// ```
+///
+/// # Inline snapshots
+///
+/// The `assert_snapshot!(value, @"expected")` form compares the value with
+/// the given literal. The expected text is normalized with [`text::trim`](fn@crate::text::trim),
+/// so it can be indented along with the code:
+///
+/// ```
+/// # use tux::assert_snapshot;
+/// let output = vec!["a", "b"].join("\n");
+/// assert_snapshot!(output, @r"
+///     a
+///     b
+/// ");
+/// ```
+///
+/// Inline snapshots are not affected by the update mode.
+///
+/// # Redactions
+///
+/// The redactions bound to the current thread with [`Redactions::bind`](crate::redact::Redactions::bind)
+/// are applied to the value before comparing it with the snapshot.
+#[macro_export]
+macro_rules! assert_snapshot {
+	($value:expr, @$expected:literal $(,)?) => {
+		$crate::assert_snapshot_inline(&$value, $expected)
+	};
+	($name:expr, $value:expr $(,)?) => {
+		$crate::assert_snapshot_file(env!("CARGO_MANIFEST_DIR"), file!(), $name, &$value)
+	};
+}
+
+/// Implementation of [`assert_snapshot!`] for inline snapshots.
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_snapshot_inline<T: std::fmt::Display + ?Sized>(value: &T, expected: &str) {
+	let actual = super::text::lines(redact(value.to_string()));
+	let expected = super::text::lines(super::text::trim(expected));
+	if actual != expected {
+		let diff = super::diff::lines(&actual, &expected);
+		let diff = diff.unified().labels("actual", "snapshot");
+		panic!("value does not match inline snapshot:\n\n{}\n", diff);
+	}
+}
+
+/// Implementation of [`assert_snapshot!`] for file snapshots.
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_snapshot_file<T: std::fmt::Display + ?Sized>(
+	manifest_dir: &str,
+	source_file: &str,
+	name: &str,
+	value: &T,
+) {
+	let snapshot_dir = snapshot_dir_for(Path::new(manifest_dir), Path::new(source_file));
+	let update = std::env::var(SNAPSHOT_UPDATE_VAR).unwrap_or_default() == "1";
+	check_snapshot(&snapshot_dir, name, &redact(value.to_string()), update);
+}
+
+/// Applies the redactions bound to the current thread, if any.
+fn redact(value: String) -> String {
+	#[cfg(feature = "redact")]
+	let value = crate::redact::apply_current(value);
+	value
+}
+
+/// Returns the snapshot directory for the given source file.
+///
+/// The path from [`file!`] is relative to the workspace root, which is not
+/// necessarily the crate root, so we search for it in the ancestors of the
+/// crate directory.
+fn snapshot_dir_for(manifest_dir: &Path, source_file: &Path) -> PathBuf {
+	let source_file = if source_file.is_absolute() {
+		source_file.to_owned()
+	} else {
+		manifest_dir
+			.ancestors()
+			.map(|dir| dir.join(source_file))
+			.find(|path| path.is_file())
+			.unwrap_or_else(|| manifest_dir.join(source_file))
+	};
+	let source_dir = source_file.parent().expect("parent dir for source file");
+	source_dir.join(SNAPSHOT_DIR)
+}
+
+#[track_caller]
+fn check_snapshot(snapshot_dir: &Path, name: &str, value: &str, update: bool) {
+	if name.is_empty() {
+		panic!("snapshot name cannot be empty");
+	}
+
+	let snapshot_file = snapshot_dir.join(format!("{}.{}", name, SNAPSHOT_FILE_EXTENSION));
+	let new_snapshot_file = snapshot_dir.join(format!("{}.{}", name, SNAPSHOT_NEW_FILE_EXTENSION));
+
+	let actual = super::text::lines(value);
+	let actual_text = actual.join("\n");
+
+	let expected = match std::fs::read_to_string(&snapshot_file) {
+		Ok(text) => Some(super::text::lines(text)),
+		Err(err) if err.kind() == ErrorKind::NotFound => None,
+		Err(err) => panic!("failed to read snapshot `{}`: {}", name, err),
+	};
+
+	if expected.as_ref() == Some(&actual) {
+		// a previously generated new snapshot is stale once the test passes
+		remove_if_exists(&new_snapshot_file);
+		return;
+	}
+
+	if update {
+		write_snapshot(&snapshot_file, &actual_text);
+		remove_if_exists(&new_snapshot_file);
+		eprintln!("updated snapshot `{}`", snapshot_file.display());
+		return;
+	}
+
+	if let Some(expected) = expected {
+		let diff = super::diff::lines(&actual, &expected);
+		let file_name = snapshot_file.file_name().unwrap().to_string_lossy();
+		let diff = diff.unified().labels(name, file_name.as_ref());
+		panic!(
+			"value does not match snapshot `{}`:\n\n{}\n\n(set {}=1 to update the snapshot)",
+			name, diff, SNAPSHOT_UPDATE_VAR
+		);
+	} else {
+		write_snapshot(&new_snapshot_file, &actual_text);
+		panic!(
+			"snapshot `{}` not found, created `{}` with the current value",
+			name,
+			new_snapshot_file.display()
+		);
+	}
+}
+
+fn write_snapshot(path: &Path, text: &str) {
+	let parent = path.parent().expect("parent dir for snapshot file");
+	std::fs::create_dir_all(parent).expect("creating snapshot directory");
+	std::fs::write(path, text).expect("writing snapshot file");
+}
+
+fn remove_if_exists(path: &Path) {
+	if let Err(err) = std::fs::remove_file(path) {
+		if err.kind() != ErrorKind::NotFound {
+			panic!("failed to remove `{}`: {}", path.display(), err);
+		}
+	}
+}
+
+#[cfg(test)]
+#[cfg(feature = "temp")] // we use `temp` in the tests
+mod test_snapshot {
+	use super::{check_snapshot, snapshot_dir_for};
+	use crate::{assert_panic, temp_dir};
+
+	#[test]
+	fn passes_if_snapshot_matches() {
+		let dir = temp_dir();
+		dir.create_file("value.snap", "line 1\nline 2\n");
+		check_snapshot(dir.path(), "value", "\nline 1  \nline 2", false);
+	}
+
+	#[test]
+	fn fails_with_diff_if_snapshot_does_not_match() {
+		let dir = temp_dir();
+		dir.create_file("value.snap", "line 1\nline 2");
+		let path = dir.path().to_owned();
+		assert_panic!("-line 3\n+line 2" in check_snapshot(&path, "value", "line 1\nline 3", false));
+	}
+
+	#[test]
+	fn fails_and_creates_new_file_if_snapshot_is_missing() {
+		let dir = temp_dir();
+		let path = dir.path().to_owned();
+		assert_panic!("snapshot `value` not found" in check_snapshot(&path, "value", "some value", false));
+
+		let new_file = dir.path().join("value.snap.new");
+		assert_eq!(std::fs::read_to_string(new_file).unwrap(), "some value");
+		assert!(!dir.path().join("value.snap").exists());
+	}
+
+	#[test]
+	fn removes_stale_new_file_once_snapshot_matches() {
+		let dir = temp_dir();
+		dir.create_file("value.snap", "some value");
+		dir.create_file("value.snap.new", "old value");
+		check_snapshot(dir.path(), "value", "some value", false);
+		assert!(!dir.path().join("value.snap.new").exists());
+	}
+
+	#[test]
+	fn update_mode_writes_missing_snapshot() {
+		let dir = temp_dir();
+		check_snapshot(&dir.path().join("snapshots"), "value", "new value", true);
+		let text = std::fs::read_to_string(dir.path().join("snapshots/value.snap")).unwrap();
+		assert_eq!(text, "new value");
+	}
+
+	#[test]
+	fn update_mode_overwrites_mismatched_snapshot() {
+		let dir = temp_dir();
+		dir.create_file("value.snap", "old value");
+		dir.create_file("value.snap.new", "old value");
+		check_snapshot(dir.path(), "value", "new value", true);
+		let text = std::fs::read_to_string(dir.path().join("value.snap")).unwrap();
+		assert_eq!(text, "new value");
+		assert!(!dir.path().join("value.snap.new").exists());
+	}
+
+	#[test]
+	fn snapshot_dir_is_next_to_source_file() {
+		let dir = temp_dir();
+		dir.create_file("crate/tests/test.rs", "");
+		let crate_dir = dir.path().join("crate");
+
+		// relative to the workspace root
+		let out = snapshot_dir_for(&crate_dir, "crate/tests/test.rs".as_ref());
+		assert_eq!(out, crate_dir.join("tests/snapshots"));
+
+		// relative to the crate root
+		let out = snapshot_dir_for(&crate_dir, "tests/test.rs".as_ref());
+		assert_eq!(out, crate_dir.join("tests/snapshots"));
+	}
+
+	#[test]
+	fn inline_snapshot() {
+		assert_snapshot!("a\n  b\nc  ", @r"
+			a
+			  b
+			c
+		");
+		assert_panic!("-x\n+a" in assert_snapshot!("x", @"a"));
+	}
+
+	#[test]
+	#[cfg(feature = "redact")]
+	fn inline_snapshot_applies_bound_redactions() {
+		let redactions = crate::redact::Redactions::builtin();
+		redactions.bind(|| {
+			assert_snapshot!("took 12ms", @"took [DURATION]");
+		});
+	}
+}
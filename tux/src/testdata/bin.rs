@@ -0,0 +1,373 @@
+use std::{
+	io::{ErrorKind, Read, Write},
+	path::Path,
+	process::{Child, ExitStatus, Output, Stdio},
+	thread::JoinHandle,
+	time::{Duration, Instant},
+};
+
+use super::{
+	new_file_for, output_result, Format, MissingValid, TestData, TestDataFileResult,
+	TestDataResult, TestDataResultItem,
+};
+
+// Changing any of these extensions requires changing the integration tests
+// that use this feature.
+const TEST_ARGS_FILE_EXTENSION: &str = "args";
+const TEST_ENV_FILE_EXTENSION: &str = "env";
+const TEST_STDOUT_FILE_EXTENSION: &str = "stdout";
+const TEST_STDERR_FILE_EXTENSION: &str = "stderr";
+const TEST_STATUS_FILE_EXTENSION: &str = "status";
+
+/// Exit status expected when there is no `.status` file.
+const SUCCESS_STATUS: &str = "0";
+
+/// Interval for checking if the binary exited, when there is a timeout.
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Output of a binary test case, compared with the file with the given
+/// extension.
+struct BinOutput {
+	extension: &'static str,
+	actual: String,
+	/// Expected value if the file does not exist, or `None` if the file is
+	/// required.
+	default: Option<&'static str>,
+}
+
+impl TestData {
+	/// Runs the tests for a binary from the project, feeding each `.input`
+	/// file to the binary as its standard input:
+	///
+	/// ```text
+	/// testdata/
+	///     hello.input     # standard input
+	///     hello.args      # arguments (optional)
+	///     hello.env       # environment variables (optional)
+	///     hello.stdout    # expected standard output
+	///     hello.stderr    # expected error output (optional)
+	///     hello.status    # expected exit code (optional)
+	/// ```
+	///
+	/// The binary is found with [`get_bin`](crate::get_bin) and runs in the
+	/// directory of the input file. The `.args` file contains whitespace
+	/// separated arguments, and the `.env` file has a `NAME=VALUE` variable
+	/// per line, with the value used as is. Empty lines and lines starting
+	/// with `#` are ignored.
+	///
+	/// The standard output, error output, and exit code are compared with the
+	/// `.stdout`, `.stderr`, and `.status` files, each one with its own diff
+	/// on failures. The outputs are normalized into lines and redacted as in
+	/// [`TestData::run`], and placeholders are supported. A missing `.stderr`
+	/// file expects no error output, and a missing `.status` file expects an
+	/// exit code of zero.
+	///
+	/// The input file is passed as is, so directives are not supported. The
+	/// binary is killed if it runs past the timeout set with [`TestData::timeout`].
+	///
+	/// If the `.stdout` file does not exist, the case fails and a
+	/// `.stdout.new` file is created with the output. In bless mode, the
+	/// mismatched files are overwritten with the actual outputs. A `.stderr`
+	/// or `.status` file that matches the default is removed instead.
+	///
+	/// See also [`testdata_bin`](super::testdata_bin).
+	pub fn run_bin(self, name: &str) {
+		let result = self.run_bin_to_result(name);
+		output_result(&result);
+	}
+
+	pub(super) fn run_bin_to_result(&self, name: &str) -> TestDataResult {
+		// fail early if the binary does not exist, instead of once per case
+		crate::get_bin(name);
+
+		let (test_inputs_with_name, skipped) = self.collect_cases();
+		let test_results = test_inputs_with_name
+			.into_iter()
+			.map(|(input_path, test_name)| {
+				let start = Instant::now();
+				let mut item = self.run_bin_case(name, &input_path, test_name);
+				item.duration = start.elapsed();
+				item
+			})
+			.collect();
+		let mut result = self.to_result(test_results, skipped);
+		self.check_stale_files(
+			&mut result,
+			&[
+				TEST_ARGS_FILE_EXTENSION,
+				TEST_ENV_FILE_EXTENSION,
+				TEST_STDOUT_FILE_EXTENSION,
+				TEST_STDERR_FILE_EXTENSION,
+				TEST_STATUS_FILE_EXTENSION,
+			],
+		);
+		result
+	}
+
+	/// Runs the binary for a single test case and compares the outputs with
+	/// the expected files.
+	fn run_bin_case(&self, name: &str, input_path: &Path, test_name: String) -> TestDataResultItem {
+		let stdout_path = self.file_with_extension(input_path, TEST_STDOUT_FILE_EXTENSION);
+		let stdout_file = stdout_path.file_name().unwrap().to_string_lossy();
+		let stdout_file = stdout_file.to_string();
+		let mut item = TestDataResultItem::new(test_name, stdout_file.clone());
+
+		let output = match self.run_bin_with_input(name, input_path) {
+			Ok(output) => output,
+			Err(err) => {
+				item.unexpected = Some(err);
+				return item;
+			}
+		};
+
+		let outputs = [
+			BinOutput {
+				extension: TEST_STDOUT_FILE_EXTENSION,
+				actual: self.bin_output_text(&output.stdout),
+				default: None,
+			},
+			BinOutput {
+				extension: TEST_STDERR_FILE_EXTENSION,
+				actual: self.bin_output_text(&output.stderr),
+				default: Some(""),
+			},
+			BinOutput {
+				extension: TEST_STATUS_FILE_EXTENSION,
+				actual: status_text(output.status),
+				default: Some(SUCCESS_STATUS),
+			},
+		];
+
+		let mut missing = false;
+		let mut mismatched = Vec::new();
+		let mut invalid_placeholder = None;
+		for it in outputs.iter() {
+			let expected_path = self.file_with_extension(input_path, it.extension);
+			let expected_file = expected_path.file_name().unwrap().to_string_lossy();
+			let expected = match std::fs::read_to_string(&expected_path) {
+				Ok(expected) => self.read_lines(expected).join("\n"),
+				Err(err) if err.kind() == ErrorKind::NotFound => match it.default {
+					Some(default) => default.to_string(),
+					None => {
+						missing = true;
+						mismatched.push(it);
+						continue;
+					}
+				},
+				Err(err) => panic!("failed to read output file for {}: {}", item.name, err),
+			};
+
+			if it.actual == expected {
+				continue;
+			}
+			match Format::Lines.matches_placeholders(it.actual.as_bytes(), expected.as_bytes()) {
+				Ok(true) => continue,
+				Ok(false) => {}
+				Err(err) => {
+					invalid_placeholder = invalid_placeholder.or(Some(format!(
+						"has an invalid placeholder in `{}` at {}",
+						expected_file, err
+					)));
+				}
+			}
+
+			if item.files.is_empty() {
+				item.valid_file = expected_file.to_string();
+			}
+			item.files.push(TestDataFileResult {
+				path: it.extension.to_string(),
+				expect: Some(Format::Lines.display_lines(expected.as_bytes())),
+				actual: Some(Format::Lines.display_lines(it.actual.as_bytes())),
+				expect_file: Some(expected_file.into()),
+			});
+			mismatched.push(it);
+		}
+
+		if missing && !self.bless && self.missing_valid == MissingValid::Fail {
+			// for convenience, if the expected output is not found we
+			// generate a new one with the current output
+			std::fs::write(new_file_for(&stdout_path), &outputs[0].actual)
+				.expect("writing new test output");
+			if item.files.is_empty() {
+				item.valid_file = stdout_file.clone();
+			} else {
+				// keep the other mismatches, so they are not only reported
+				// once the `.stdout` file is created
+				item.files.insert(
+					0,
+					TestDataFileResult {
+						path: TEST_STDOUT_FILE_EXTENSION.to_string(),
+						expect: None,
+						actual: Some(Format::Lines.display_lines(outputs[0].actual.as_bytes())),
+						expect_file: Some(stdout_file.clone()),
+					},
+				);
+				item.unexpected = invalid_placeholder;
+			}
+		} else {
+			// outside bless mode, only a missing `.stdout` file is created
+			// (see `MissingValid::Create`), and mismatched files are kept
+			for it in mismatched
+				.iter()
+				.filter(|x| self.bless || (missing && x.default.is_none()))
+			{
+				let expected_path = self.file_with_extension(input_path, it.extension);
+				if Some(it.actual.as_str()) == it.default {
+					std::fs::remove_file(expected_path).expect("removing outdated test output");
+				} else {
+					std::fs::write(expected_path, &it.actual).expect("writing blessed test output");
+				}
+			}
+			if self.bless {
+				item.files.clear();
+			}
+			item.success = item.files.is_empty();
+			item.blessed = item.success && !mismatched.is_empty();
+			if item.blessed {
+				item.valid_file = stdout_file.clone();
+			} else if !item.success {
+				item.unexpected = invalid_placeholder;
+			}
+		}
+
+		if item.success {
+			// any `.stdout.new` file is stale once the test passes
+			if let Err(err) = std::fs::remove_file(new_file_for(&stdout_path)) {
+				if err.kind() != ErrorKind::NotFound {
+					panic!("failed to remove stale output for {}: {}", item.name, err);
+				}
+			}
+		}
+
+		item
+	}
+
+	/// Runs the binary with the input file as standard input, and the
+	/// arguments and environment variables from the sidecar files. The binary
+	/// is killed if it runs past the timeout.
+	fn run_bin_with_input(&self, name: &str, input_path: &Path) -> Result<Output, String> {
+		let input = std::fs::read(input_path).expect("reading test input file");
+
+		let mut cmd = crate::get_bin(name);
+		if let Some(args) = self.read_bin_file(input_path, TEST_ARGS_FILE_EXTENSION) {
+			for line in args.lines().filter(|x| !is_comment(x)) {
+				cmd.args(line.split_whitespace());
+			}
+		}
+		if let Some(env) = self.read_bin_file(input_path, TEST_ENV_FILE_EXTENSION) {
+			for (index, line) in env.lines().enumerate().filter(|(_, x)| !is_comment(x)) {
+				let Some((var, value)) = line.split_once('=') else {
+					let env_path = self.file_with_extension(input_path, TEST_ENV_FILE_EXTENSION);
+					return Err(format!(
+						"has an invalid line in `{}` at line {}: expected `NAME=VALUE`",
+						env_path.file_name().unwrap().to_string_lossy(),
+						index + 1
+					));
+				};
+				cmd.env(var.trim(), value);
+			}
+		}
+		if let Some(dir) = input_path.parent().filter(|x| !x.as_os_str().is_empty()) {
+			cmd.current_dir(dir);
+		}
+
+		cmd.stdin(Stdio::piped())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped());
+		let mut child = cmd
+			.spawn()
+			.map_err(|err| format!("failed to run `{}`: {}", name, err))?;
+
+		// the input is written from another thread, since the binary may block
+		// writing its output before reading all of the input
+		let mut stdin = child.stdin.take().unwrap();
+		let writer = std::thread::spawn(move || {
+			// the binary is free to ignore the input and exit early
+			let _ = stdin.write_all(&input);
+		});
+		let stdout = read_output(child.stdout.take().unwrap());
+		let stderr = read_output(child.stderr.take().unwrap());
+
+		let status = wait_with_timeout(&mut child, self.timeout)
+			.map_err(|err| format!("failed to run `{}`: {}", name, err));
+		writer.join().expect("writing test input");
+		let stdout = stdout.join().expect("reading test output");
+		let stderr = stderr.join().expect("reading test output");
+		match status? {
+			Some(status) => Ok(Output {
+				status,
+				stdout,
+				stderr,
+			}),
+			None => Err(format!(
+				"timed out after {:?}",
+				self.timeout.unwrap_or_default()
+			)),
+		}
+	}
+
+	/// Reads an optional sidecar file for the input.
+	fn read_bin_file(&self, input_path: &Path, extension: &str) -> Option<String> {
+		let path = self.file_with_extension(input_path, extension);
+		match std::fs::read_to_string(path) {
+			Ok(text) => Some(text),
+			Err(err) if err.kind() == ErrorKind::NotFound => None,
+			Err(err) => panic!("failed to read `{}` file: {}", extension, err),
+		}
+	}
+
+	/// Returns the redacted output text, normalized into lines.
+	fn bin_output_text(&self, output: &[u8]) -> String {
+		let output = String::from_utf8_lossy(output).into_owned();
+		let output = self.redact_text(output);
+		self.read_lines(output).join("\n")
+	}
+}
+
+/// Reads the output of the binary from another thread, so that neither the
+/// standard output nor the error output blocks the binary when full.
+fn read_output<R: Read + Send + 'static>(mut output: R) -> JoinHandle<Vec<u8>> {
+	std::thread::spawn(move || {
+		let mut buffer = Vec::new();
+		let _ = output.read_to_end(&mut buffer);
+		buffer
+	})
+}
+
+/// Waits for the binary to exit, killing it if it runs past the timeout.
+/// Returns `None` if the binary was killed.
+fn wait_with_timeout(
+	child: &mut Child,
+	timeout: Option<Duration>,
+) -> std::io::Result<Option<ExitStatus>> {
+	let Some(timeout) = timeout else {
+		return child.wait().map(Some);
+	};
+
+	let deadline = Instant::now() + timeout;
+	loop {
+		if let Some(status) = child.try_wait()? {
+			return Ok(Some(status));
+		}
+		if Instant::now() >= deadline {
+			child.kill()?;
+			child.wait()?;
+			return Ok(None);
+		}
+		std::thread::sleep(WAIT_INTERVAL);
+	}
+}
+
+/// Returns the text for the exit status, which is the exit code unless the
+/// process was terminated by a signal.
+fn status_text(status: ExitStatus) -> String {
+	match status.code() {
+		Some(code) => code.to_string(),
+		None => status.to_string(),
+	}
+}
+
+fn is_comment(line: &str) -> bool {
+	let line = line.trim();
+	line.is_empty() || line.starts_with('#')
+}
@@ -0,0 +1,1146 @@
+use std::{
+	io::ErrorKind,
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		mpsc::{self, RecvTimeoutError},
+		Arc, Mutex,
+	},
+	time::{Duration, Instant},
+};
+
+#[cfg(feature = "redact")]
+use crate::redact::Redactions;
+
+use super::{
+	catch_panic, collect_test_inputs_with_name, matches_filter, parse_test_case,
+	suppress_panic_output, write_reports_from_env, Format, TestCase, TestDataResult,
+	TestDataResultItem, Watchdog,
+};
+
+// Changing any of these extensions requires changing all unit and integration
+// tests that use this feature, and the `testdata` tests themselves.
+const TEST_INPUT_FILE_EXTENSION: &str = "input";
+const TEST_VALID_FILE_EXTENSION: &str = "valid";
+const TEST_ERROR_FILE_EXTENSION: &str = "error";
+const TEST_NEW_FILE_EXTENSION: &str = "new";
+pub(super) const TEST_CHECK_FILE_EXTENSION: &str = "check";
+
+/// Environment variable that enables the bless mode for [`testdata`](super::testdata).
+pub const TESTDATA_BLESS_VAR: &str = "TUX_BLESS";
+
+/// Environment variable with a filter pattern for the test cases that
+/// [`testdata`](super::testdata) runs. See [`TestData::filter`].
+pub const TESTDATA_FILTER_VAR: &str = "TUX_TESTDATA_FILTER";
+
+/// Environment variable that enables the strict mode for [`testdata`](super::testdata).
+/// See [`TestData::strict`].
+pub const TESTDATA_STRICT_VAR: &str = "TUX_TESTDATA_STRICT";
+
+/// Determines what happens to a test case when the valid file with the
+/// expected output is not found. See [`TestData::missing_valid`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MissingValid {
+	/// The test fails, and a `.valid.new` file is created with the actual
+	/// test output. This is the default.
+	Fail,
+
+	/// The valid file is created with the actual test output, and the test
+	/// does not fail.
+	Create,
+}
+
+/// Configures and runs file based tests. This is the configurable version of
+/// [`testdata`](super::testdata), which uses the default configuration.
+///
+/// See [`testdata`](super::testdata) for details on the test procedure.
+///
+/// # Example
+///
+/// ```no_run
+/// use tux::{MissingValid, TestData};
+///
+/// TestData::new("tests/testdata/format")
+///     .filter("parser/if*")
+///     .input_extension("in")
+///     .valid_extension("out")
+///     .trim(false)
+///     .missing_valid(MissingValid::Fail)
+///     .run(|lines| lines);
+/// ```
+#[derive(Clone)]
+pub struct TestData {
+	pub(super) path: PathBuf,
+	pub(super) input_extension: String,
+	valid_extension: String,
+	error_extension: String,
+	trim: bool,
+	pub(super) missing_valid: MissingValid,
+	pub(super) bless: bool,
+	filter: Option<String>,
+	pub(super) case: Option<String>,
+	#[cfg(feature = "redact")]
+	redactions: Option<Redactions>,
+	pub(super) strict: bool,
+	pub(super) timeout: Option<Duration>,
+	pub(super) input_checks: bool,
+}
+
+impl TestData {
+	/// Creates a new configuration for the tests in the given directory,
+	/// with the default settings:
+	///
+	/// - Test inputs are `.input` files and the expected output is read from
+	///   `.valid` files. Expected errors are read from `.error` files.
+	/// - Input and output lines are trimmed.
+	/// - Missing `.valid` files fail the test ([`MissingValid::Fail`]).
+	/// - Bless mode is enabled if the `TUX_BLESS` environment variable is
+	///   set to `1`.
+	/// - Test cases are filtered by the `TUX_TESTDATA_FILTER` environment
+	///   variable, if set to a non-empty pattern.
+	/// - Strict mode is enabled if the `TUX_TESTDATA_STRICT` environment
+	///   variable is set to `1`.
+	pub fn new<P: AsRef<Path>>(path: P) -> Self {
+		let mut data = Self::without_env(path);
+		data.bless = std::env::var(TESTDATA_BLESS_VAR).unwrap_or_default() == "1";
+		data.strict = std::env::var(TESTDATA_STRICT_VAR).unwrap_or_default() == "1";
+		data.filter = std::env::var(TESTDATA_FILTER_VAR)
+			.ok()
+			.filter(|x| !x.is_empty());
+		data
+	}
+
+	/// Creates a new configuration with the default settings, ignoring the
+	/// environment variables read by [`TestData::new`].
+	///
+	/// This is used by the tests for this module, which must not be affected
+	/// by the environment of the test run (e.g. `TUX_BLESS=1 cargo test`).
+	pub(super) fn without_env<P: AsRef<Path>>(path: P) -> Self {
+		TestData {
+			path: path.as_ref().to_owned(),
+			input_extension: TEST_INPUT_FILE_EXTENSION.into(),
+			valid_extension: TEST_VALID_FILE_EXTENSION.into(),
+			error_extension: TEST_ERROR_FILE_EXTENSION.into(),
+			trim: true,
+			missing_valid: MissingValid::Fail,
+			bless: false,
+			filter: None,
+			case: None,
+			#[cfg(feature = "redact")]
+			redactions: None,
+			strict: false,
+			timeout: None,
+			input_checks: false,
+		}
+	}
+
+	/// Sets the extension for the test input files, without the leading dot.
+	/// The extension can contain multiple components (e.g. `in.txt`).
+	pub fn input_extension<S: Into<String>>(mut self, extension: S) -> Self {
+		self.input_extension = extension.into();
+		self
+	}
+
+	/// Sets the extension for the files with the expected output, without
+	/// the leading dot. The extension replaces the input extension in the
+	/// input file name.
+	///
+	/// New files generated for missing valid files use this extension with
+	/// a `.new` suffix.
+	pub fn valid_extension<S: Into<String>>(mut self, extension: S) -> Self {
+		self.valid_extension = extension.into();
+		self
+	}
+
+	/// Sets the extension for the files with the expected error, without the
+	/// leading dot. This is used by tests with a fallible callback (see
+	/// [`TestData::run_fallible`]).
+	pub fn error_extension<S: Into<String>>(mut self, extension: S) -> Self {
+		self.error_extension = extension.into();
+		self
+	}
+
+	/// Enables or disables the normalization of input and output lines with
+	/// [`text::lines`](fn@crate::text::lines). This is enabled by default.
+	///
+	/// When disabled, files are split into lines with [`text::split_lines`](fn@crate::text::split_lines),
+	/// which preserves all whitespace, and the comparison is exact.
+	pub fn trim(mut self, enabled: bool) -> Self {
+		self.trim = enabled;
+		self
+	}
+
+	/// Sets how to handle a missing valid file (see [`MissingValid`]).
+	pub fn missing_valid(mut self, mode: MissingValid) -> Self {
+		self.missing_valid = mode;
+		self
+	}
+
+	/// Enables or disables the bless mode, overriding the `TUX_BLESS`
+	/// environment variable.
+	///
+	/// In bless mode, mismatched or missing valid files are overwritten with
+	/// the actual test output and the test does not fail.
+	pub fn bless(mut self, enabled: bool) -> Self {
+		self.bless = enabled;
+		self
+	}
+
+	/// Enables or disables the strict mode, overriding the `TUX_TESTDATA_STRICT`
+	/// environment variable.
+	///
+	/// After running the test cases, the test directory is checked for
+	/// expected files without a matching input (e.g. a `.valid` file whose
+	/// input was renamed) and for `.new` files left over from a previous run
+	/// next to the file they were meant to replace.
+	///
+	/// By default, these files are reported as warnings. In strict mode, each
+	/// file is reported as a failed test case instead.
+	pub fn strict(mut self, enabled: bool) -> Self {
+		self.strict = enabled;
+		self
+	}
+
+	/// Sets the default timeout for each test case, which can be overridden
+	/// by the `timeout` directive in the input file (see [`TestCase::timeout`]).
+	/// There is no timeout by default.
+	///
+	/// A test case that runs past its timeout fails once the callback returns,
+	/// and the remaining test cases continue running. This is not a hard
+	/// timeout: the callback cannot be interrupted, so with [`TestData::run`]
+	/// and the other run methods, a callback that never returns hangs the run
+	/// and is only reported by name.
+	///
+	/// Hard timeouts require [`TestData::run_isolated`], which stops waiting
+	/// for a test case once it runs past the timeout. For [`TestData::run_bin`],
+	/// the binary is killed once it runs past the timeout.
+	///
+	/// ```no_run
+	/// use std::time::Duration;
+	/// use tux::TestData;
+	///
+	/// TestData::new("tests/testdata/solver")
+	///     .timeout(Duration::from_secs(5))
+	///     .run(|lines| lines);
+	/// ```
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = Some(timeout);
+		self
+	}
+
+	/// Enables or disables the check directives in the comments of the input
+	/// files (e.g. `// CHECK: text`), for test cases without a `.valid` or a
+	/// `.check` file. This is disabled by default, since the input may
+	/// contain text that looks like a directive.
+	///
+	/// The input is passed to the callback as is, including the comments.
+	pub fn input_checks(mut self, enabled: bool) -> Self {
+		self.input_checks = enabled;
+		self
+	}
+
+	/// Runs only the test cases with a name matching the pattern, overriding
+	/// the `TUX_TESTDATA_FILTER` environment variable.
+	///
+	/// The test name is the input file path relative to the test directory,
+	/// using `/` as separator (e.g. `parser/if_else.input`).
+	///
+	/// A pattern containing `*` or `?` is a glob that must match the whole
+	/// name, with `*` matching any sequence of characters and `?` matching a
+	/// single character. Otherwise, the pattern matches any name containing
+	/// it. An empty pattern runs all test cases.
+	///
+	/// Skipped test cases are reported in the test summary.
+	pub fn filter<S: Into<String>>(mut self, pattern: S) -> Self {
+		self.filter = Some(pattern.into());
+		self
+	}
+
+	/// Sets the redactions applied to the test output before comparing it
+	/// with the expected output, so that volatile values such as temporary
+	/// paths or timestamps can be replaced with placeholders.
+	///
+	/// By default, the redactions bound to the current thread with
+	/// [`Redactions::bind`] are used, if any. Binary output from
+	/// [`TestData::run_bytes`] is not redacted.
+	///
+	/// This requires the `redact` feature (enabled by default).
+	///
+	/// ```no_run
+	/// use tux::{redact::Redactions, TestData};
+	///
+	/// TestData::new("tests/testdata/build")
+	///     .redact(Redactions::builtin().rule("port", r"port \d+", "port [PORT]"))
+	///     .run(|lines| lines);
+	/// ```
+	#[cfg(feature = "redact")]
+	pub fn redact(mut self, redactions: Redactions) -> Self {
+		self.redactions = Some(redactions);
+		self
+	}
+
+	/// Runs only the test case with the given name. This is used by the tests
+	/// generated with the [`testdata`](macro@crate::testdata) attribute, and
+	/// ignores the filter pattern.
+	pub(super) fn case<S: Into<String>>(mut self, name: S) -> Self {
+		self.case = Some(name.into());
+		self
+	}
+
+	/// Runs the tests with the given callback, outputs the test summary and
+	/// panics if any test case fails.
+	pub fn run<F>(self, callback: F)
+	where
+		F: FnMut(Vec<String>) -> Vec<String>,
+	{
+		let result = self.run_to_result(callback);
+		output_result(&result);
+	}
+
+	/// Runs the tests with a callback that also receives the [`TestCase`],
+	/// with the test name and the directives from the input file header.
+	///
+	/// See also [`testdata_with_case`](super::testdata_with_case).
+	pub fn run_with_case<F>(self, callback: F)
+	where
+		F: FnMut(&TestCase, Vec<String>) -> Vec<String>,
+	{
+		let result = self.run_with_case_to_result(callback);
+		output_result(&result);
+	}
+
+	/// Runs the test cases in parallel with the given callback, outputs the
+	/// test summary and panics if any test case fails.
+	///
+	/// The test cases are distributed among a number of threads given by
+	/// [`std::thread::available_parallelism`]. The test summary and failure
+	/// output are the same as [`TestData::run`], in the same order.
+	pub fn run_parallel<F>(self, callback: F)
+	where
+		F: Fn(Vec<String>) -> Vec<String> + Sync,
+	{
+		let result = self.run_parallel_to_result(callback);
+		output_result(&result);
+	}
+
+	/// Runs each test case on its own thread with the given callback, outputs
+	/// the test summary and panics if any test case fails.
+	///
+	/// The test cases still run one at a time, but a test case that runs past
+	/// its timeout (see [`TestData::timeout`]) fails without waiting for the
+	/// callback to return, so the remaining test cases run even if it is
+	/// stuck. The thread for the timed out test case is left running in the
+	/// background.
+	pub fn run_isolated<F>(self, callback: F)
+	where
+		F: Fn(Vec<String>) -> Vec<String> + Send + Sync + 'static,
+	{
+		let result = self.run_isolated_to_result(callback);
+		output_result(&result);
+	}
+
+	/// Runs the tests with a fallible callback, outputs the test summary and
+	/// panics if any test case fails.
+	///
+	/// An `Ok` output is compared with the `.valid` file, as in [`TestData::run`].
+	/// An `Err` is formatted with [`Display`](std::fmt::Display) and compared
+	/// with the `.error` file instead. The error is normalized into lines in
+	/// the same way as the valid file.
+	///
+	/// A test case fails if it returns an error but only the `.valid` file
+	/// exists, or if it succeeds but only the `.error` file exists. If neither
+	/// exists, the test fails and a `.valid.new` or `.error.new` file is
+	/// created with the actual output.
+	///
+	/// See also [`testdata_fallible`](super::testdata_fallible).
+	pub fn run_fallible<F, E>(self, callback: F)
+	where
+		F: FnMut(Vec<String>) -> Result<Vec<String>, E>,
+		E: std::fmt::Display,
+	{
+		let result = self.run_fallible_to_result(callback);
+		output_result(&result);
+	}
+
+	/// Runs the tests with a callback receiving the raw input text, without
+	/// the line normalization. The output is compared exactly with the valid
+	/// file, including any whitespace and line breaks. The [`TestData::trim`]
+	/// setting does not apply.
+	///
+	/// On failures, whitespace in the diff is made visible: spaces are shown
+	/// as `·`, tabs as `→`, carriage returns as `␍`, and line breaks as `␊`.
+	///
+	/// See also [`testdata_raw`](super::testdata_raw).
+	pub fn run_raw<F>(self, mut callback: F)
+	where
+		F: FnMut(String) -> String,
+	{
+		let result = self.run_raw_to_result(&mut callback);
+		output_result(&result);
+	}
+
+	/// Runs the tests with a callback receiving the input file bytes. The
+	/// output is compared exactly with the valid file.
+	///
+	/// On failures, the diff is a hex dump of both the output and the valid
+	/// file contents.
+	///
+	/// See also [`testdata_bytes`](super::testdata_bytes).
+	pub fn run_bytes<F>(self, callback: F)
+	where
+		F: FnMut(Vec<u8>) -> Vec<u8>,
+	{
+		let result = self.run_bytes_to_result(callback);
+		output_result(&result);
+	}
+
+	/// Runs the test cases the same as [`TestData::run`], but returns the
+	/// result instead of outputting it and panicking on failures.
+	///
+	/// This can be used to inspect the results or to generate reports with
+	/// [`TestDataResult::write_reports`].
+	pub fn run_to_result<F>(&self, mut test_callback: F) -> TestDataResult
+	where
+		F: FnMut(Vec<String>) -> Vec<String>,
+	{
+		self.run_cases(Format::Lines, |_, input| {
+			Ok(self.call_with_lines(input, &mut test_callback))
+		})
+	}
+
+	pub(super) fn run_with_case_to_result<F>(&self, mut test_callback: F) -> TestDataResult
+	where
+		F: FnMut(&TestCase, Vec<String>) -> Vec<String>,
+	{
+		self.run_cases(Format::Lines, |case, input| {
+			Ok(self.call_with_lines(input, |input| test_callback(case, input)))
+		})
+	}
+
+	pub(super) fn run_fallible_to_result<F, E>(&self, mut test_callback: F) -> TestDataResult
+	where
+		F: FnMut(Vec<String>) -> Result<Vec<String>, E>,
+		E: std::fmt::Display,
+	{
+		self.run_cases(Format::Lines, |_, input| {
+			let input = String::from_utf8(input).expect("reading test input file as utf-8");
+			match test_callback(self.read_lines(input)) {
+				Ok(output) => Ok(output.join("\n").into_bytes()),
+				Err(error) => Err(error.to_string()),
+			}
+		})
+	}
+
+	pub(super) fn run_raw_to_result<F>(&self, mut test_callback: F) -> TestDataResult
+	where
+		F: FnMut(String) -> String,
+	{
+		self.run_cases(Format::Raw, |_, input| {
+			let input = String::from_utf8(input).expect("reading test input file as utf-8");
+			Ok(test_callback(input).into_bytes())
+		})
+	}
+
+	pub(super) fn run_bytes_to_result<F>(&self, mut test_callback: F) -> TestDataResult
+	where
+		F: FnMut(Vec<u8>) -> Vec<u8>,
+	{
+		self.run_cases(Format::Bytes, |_, input| Ok(test_callback(input)))
+	}
+
+	pub(super) fn run_parallel_to_result<F>(&self, test_callback: F) -> TestDataResult
+	where
+		F: Fn(Vec<String>) -> Vec<String> + Sync,
+	{
+		let (test_inputs_with_name, skipped) = self.collect_cases();
+
+		let thread_count = std::thread::available_parallelism()
+			.map(|x| x.get())
+			.unwrap_or(1);
+		let thread_count = thread_count.min(test_inputs_with_name.len());
+
+		// each thread takes the next case from the list until it is done, and
+		// stores the result by index to keep the reporting order
+		let next_case = AtomicUsize::new(0);
+		let watchdog = Watchdog::new();
+		#[cfg(feature = "redact")]
+		let redactions = crate::redact::current();
+		let test_results = Mutex::new(Vec::new());
+		std::thread::scope(|scope| {
+			for _ in 0..thread_count {
+				scope.spawn(|| {
+					// the redactions bound to the caller thread apply to all threads
+					#[cfg(feature = "redact")]
+					let _redactions = crate::redact::set_current(redactions.clone());
+					let _hook = suppress_panic_output();
+					loop {
+						let index = next_case.fetch_add(1, Ordering::Relaxed);
+						let Some((input_path, test_name)) = test_inputs_with_name.get(index) else {
+							break;
+						};
+						let start = Instant::now();
+						let mut item = self.run_case(
+							input_path,
+							test_name.clone(),
+							Format::Lines,
+							Some(&watchdog),
+							|_, input| Ok(self.call_with_lines(input, &test_callback)),
+						);
+						item.duration = start.elapsed();
+						test_results.lock().unwrap().push((index, item));
+					}
+				});
+			}
+		});
+
+		let mut test_results = test_results.into_inner().unwrap();
+		test_results.sort_by_key(|(index, _)| *index);
+		let test_results = test_results.into_iter().map(|(_, item)| item).collect();
+		let mut result = self.to_result(test_results, skipped);
+		self.check_stale_files(&mut result, &self.expected_extensions());
+		result
+	}
+
+	/// Returns the extensions for the files with the expected output of the
+	/// `.input` files.
+	fn expected_extensions(&self) -> [&str; 3] {
+		[
+			&self.valid_extension,
+			&self.error_extension,
+			TEST_CHECK_FILE_EXTENSION,
+		]
+	}
+
+	pub(super) fn run_isolated_to_result<F>(&self, test_callback: F) -> TestDataResult
+	where
+		F: Fn(Vec<String>) -> Vec<String> + Send + Sync + 'static,
+	{
+		let (test_inputs_with_name, skipped) = self.collect_cases();
+		let test_callback = Arc::new(test_callback);
+		#[cfg(feature = "redact")]
+		let redactions = crate::redact::current();
+		let test_results = test_inputs_with_name
+			.into_iter()
+			.map(|(input_path, test_name)| {
+				let start = Instant::now();
+				let (case, input) = match self.read_case(&input_path, &test_name, Format::Lines) {
+					Ok(result) => result,
+					Err(err) => {
+						let mut item = self.item_without_output(&input_path, test_name);
+						item.unexpected = Some(err);
+						return item;
+					}
+				};
+				let timeout = case.timeout().or(self.timeout);
+
+				// the thread is detached, so it can be left running on a timeout
+				let (sender, receiver) = mpsc::channel();
+				let data = self.clone();
+				let callback = test_callback.clone();
+				#[cfg(feature = "redact")]
+				let redactions = redactions.clone();
+				let path = input_path.clone();
+				let name = test_name.clone();
+				std::thread::spawn(move || {
+					#[cfg(feature = "redact")]
+					let _redactions = crate::redact::set_current(redactions);
+					let _hook = suppress_panic_output();
+					let item = data.run_case_input(
+						&path,
+						name,
+						Format::Lines,
+						&case,
+						input,
+						None,
+						|_, input| Ok(data.call_with_lines(input, &*callback)),
+					);
+					let _ = sender.send(item);
+				});
+
+				let received = match timeout {
+					Some(timeout) => receiver.recv_timeout(timeout),
+					None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+				};
+				let mut item = match received {
+					Ok(item) => item,
+					Err(err) => {
+						let mut item = self.item_without_output(&input_path, test_name);
+						item.unexpected = Some(match err {
+							RecvTimeoutError::Timeout => {
+								format!("timed out after {:?}", timeout.unwrap_or_default())
+							}
+							RecvTimeoutError::Disconnected => "stopped without a result".into(),
+						});
+						item
+					}
+				};
+				item.duration = start.elapsed();
+				item
+			})
+			.collect();
+		let mut result = self.to_result(test_results, skipped);
+		self.check_stale_files(&mut result, &self.expected_extensions());
+		result
+	}
+
+	/// Runs all test cases in order with the given callback.
+	fn run_cases<F>(&self, format: Format, mut test_callback: F) -> TestDataResult
+	where
+		F: FnMut(&TestCase, Vec<u8>) -> Result<Vec<u8>, String>,
+	{
+		let (test_inputs_with_name, skipped) = self.collect_cases();
+		let watchdog = Watchdog::new();
+		let _hook = suppress_panic_output();
+		let test_results = test_inputs_with_name
+			.into_iter()
+			.map(|(input_path, test_name)| {
+				let start = Instant::now();
+				let mut item = self.run_case(
+					&input_path,
+					test_name,
+					format,
+					Some(&watchdog),
+					&mut test_callback,
+				);
+				item.duration = start.elapsed();
+				item
+			})
+			.collect();
+		let mut result = self.to_result(test_results, skipped);
+		self.check_stale_files(&mut result, &self.expected_extensions());
+		result
+	}
+
+	/// Calls a line based test callback with the file contents.
+	fn call_with_lines<F>(&self, input: Vec<u8>, test_callback: F) -> Vec<u8>
+	where
+		F: FnOnce(Vec<String>) -> Vec<String>,
+	{
+		let input = String::from_utf8(input).expect("reading test input file as utf-8");
+		let output = test_callback(self.read_lines(input));
+		output.join("\n").into_bytes()
+	}
+
+	/// Returns the path and name of the test cases to run, and the names of
+	/// the test cases skipped by the filter.
+	pub(super) fn collect_cases(&self) -> (Vec<(PathBuf, String)>, Vec<String>) {
+		let mut test_cases = Vec::new();
+		let mut skipped = Vec::new();
+		let test_inputs_with_name =
+			collect_test_inputs_with_name(&self.path, &self.input_extension);
+
+		for (input_path, test_name) in test_inputs_with_name.into_iter() {
+			if let Some(case) = &self.case {
+				if &test_name != case {
+					continue;
+				}
+			} else if !self.matches_filter(&test_name) {
+				skipped.push(test_name);
+				continue;
+			}
+			test_cases.push((input_path, test_name));
+		}
+
+		if let Some(case) = &self.case {
+			if test_cases.is_empty() {
+				panic!(
+					"test case `{}` not found in `{}`",
+					case,
+					self.path.display()
+				);
+			}
+		}
+
+		(test_cases, skipped)
+	}
+
+	/// Returns `true` if the test name matches the filter pattern, if any.
+	pub(super) fn matches_filter(&self, test_name: &str) -> bool {
+		match &self.filter {
+			Some(filter) => matches_filter(test_name, filter),
+			None => true,
+		}
+	}
+
+	pub(super) fn to_result(
+		&self,
+		tests: Vec<TestDataResultItem>,
+		skipped: Vec<String>,
+	) -> TestDataResult {
+		let name = match &self.case {
+			Some(case) => format!("{}/{}", self.path.display(), case),
+			None => self.path.display().to_string(),
+		};
+		TestDataResult {
+			name,
+			tests,
+			skipped,
+			filter: self.filter.clone(),
+			warnings: Vec::new(),
+		}
+	}
+
+	/// Runs a single test case, applying the directives from the input file
+	/// header for line based tests. The callback is supervised by the
+	/// watchdog, if any.
+	fn run_case<F>(
+		&self,
+		input_path: &Path,
+		test_name: String,
+		format: Format,
+		watchdog: Option<&Watchdog>,
+		test_callback: F,
+	) -> TestDataResultItem
+	where
+		F: FnOnce(&TestCase, Vec<u8>) -> Result<Vec<u8>, String>,
+	{
+		match self.read_case(input_path, &test_name, format) {
+			Ok((case, input)) => self.run_case_input(
+				input_path,
+				test_name,
+				format,
+				&case,
+				input,
+				watchdog,
+				test_callback,
+			),
+			Err(err) => {
+				let mut item = self.item_without_output(input_path, test_name);
+				item.unexpected = Some(err);
+				item
+			}
+		}
+	}
+
+	/// Reads the input file for a test case, and parses the directives from
+	/// the header for line based tests.
+	fn read_case(
+		&self,
+		input_path: &Path,
+		test_name: &str,
+		format: Format,
+	) -> Result<(TestCase, Vec<u8>), String> {
+		let input = std::fs::read(input_path).expect("reading test input file");
+
+		// directives only apply to line based tests, since for the others the
+		// input must be passed as is
+		if format == Format::Lines {
+			let input = String::from_utf8_lossy(&input);
+			parse_test_case(test_name, &input)
+				.map(|(case, input)| (case, input.as_bytes().to_vec()))
+		} else {
+			Ok((TestCase::default(), input))
+		}
+	}
+
+	/// Runs a single test case with the input from [`TestData::read_case`].
+	#[allow(clippy::too_many_arguments)]
+	fn run_case_input<F>(
+		&self,
+		input_path: &Path,
+		test_name: String,
+		format: Format,
+		case: &TestCase,
+		input: Vec<u8>,
+		watchdog: Option<&Watchdog>,
+		test_callback: F,
+	) -> TestDataResultItem
+	where
+		F: FnOnce(&TestCase, Vec<u8>) -> Result<Vec<u8>, String>,
+	{
+		if case.skip() {
+			let mut item = self.item_without_output(input_path, test_name);
+			item.success = true;
+			item.skipped = Some(case.skip_reason().unwrap_or_default().to_string());
+			return item;
+		}
+
+		// check directives are only used when there is no valid file
+		let checks = if format == Format::Lines {
+			let input_text = String::from_utf8_lossy(&input);
+			match self.checks_for(input_path, &input_text) {
+				Ok(checks) => checks,
+				Err(err) => {
+					let mut item = self.item_without_output(input_path, test_name);
+					item.unexpected = Some(err);
+					return item;
+				}
+			}
+		} else {
+			None
+		};
+
+		let watch = watchdog.map(|x| x.watch(&test_name, case.timeout().or(self.timeout)));
+		let outcome = catch_panic(|| test_callback(case, input));
+		if let Some(timeout) = watch.and_then(|x| x.timed_out()) {
+			let mut item = self.item_without_output(input_path, test_name);
+			item.unexpected = Some(format!("timed out after {:?}", timeout));
+			return item;
+		}
+
+		let item = match checks {
+			Some(checks) => self.run_case_checks(test_name, &checks, outcome),
+			None => self.run_case_callback(input_path, test_name, format, case, outcome),
+		};
+		expect_fail_result(case, item)
+	}
+
+	/// Returns a failed result item for a test case without output.
+	fn item_without_output(&self, input_path: &Path, test_name: String) -> TestDataResultItem {
+		let valid_file_path = self.valid_file_for(input_path);
+		let valid_file_name = valid_file_path.file_name().unwrap().to_string_lossy();
+		TestDataResultItem::new(test_name, valid_file_name.into())
+	}
+
+	/// Compares the outcome of the callback for a single test case with the
+	/// valid file, or the error with the error file. The outcome is an error
+	/// with the panic message if the callback panicked.
+	fn run_case_callback(
+		&self,
+		input_path: &Path,
+		test_name: String,
+		format: Format,
+		case: &TestCase,
+		outcome: Result<Result<Vec<u8>, String>, String>,
+	) -> TestDataResultItem {
+		let valid_file_path = self.valid_file_for(input_path);
+		let error_file_path = self.error_file_for(input_path);
+
+		// the expected output of a failing case is not generated nor blessed
+		let expect_fail = case.expect_fail();
+		let bless = self.bless && !expect_fail;
+
+		let outcome = match outcome {
+			Ok(outcome) => outcome,
+			Err(panic) => {
+				let mut item = self.item_without_output(input_path, test_name);
+				item.panic = Some(panic);
+				return item;
+			}
+		};
+
+		let outcome = match outcome {
+			Ok(output) => Ok(self.redact_output(format, output)),
+			Err(error) => Err(self.redact_text(error)),
+		};
+
+		// an error is compared with the error file instead of the valid file
+		let is_error = outcome.is_err();
+		let (output, format, expected_file_path, other_file_path) = match outcome {
+			Ok(output) => (output, format, &valid_file_path, &error_file_path),
+			Err(error) => {
+				let error = self.read_lines(error).join("\n");
+				(
+					error.into_bytes(),
+					Format::Lines,
+					&error_file_path,
+					&valid_file_path,
+				)
+			}
+		};
+		let new_file_path = new_file_for(expected_file_path);
+		let expected_file_name = expected_file_path.file_name().unwrap().to_string_lossy();
+
+		let mut test_succeeded = true;
+		let mut test_blessed = false;
+		let mut unexpected = None;
+		let mut invalid_placeholder = None;
+
+		let expected = match std::fs::read(expected_file_path) {
+			Ok(raw_expected) => {
+				let expected = self.normalize_expected(format, raw_expected);
+				if output != expected {
+					match format.matches_placeholders(&output, &expected) {
+						Ok(matched) => test_succeeded = matched,
+						Err(err) => {
+							invalid_placeholder = Some(format!(
+								"has an invalid placeholder in `{}` at {}",
+								expected_file_name, err
+							));
+							test_succeeded = false;
+						}
+					}
+				}
+				Some(expected)
+			}
+			Err(err) => {
+				if err.kind() == ErrorKind::NotFound {
+					if other_file_path != expected_file_path && other_file_path.is_file() {
+						let other_file_name =
+							other_file_path.file_name().unwrap().to_string_lossy();
+						unexpected = Some(if is_error {
+							format!(
+								"failed with an error, but `{}` expects an output",
+								other_file_name
+							)
+						} else {
+							format!("succeeded, but `{}` expects an error", other_file_name)
+						});
+					} else if self.missing_valid == MissingValid::Create {
+						test_blessed = true;
+					} else if !bless && !expect_fail {
+						// for convenience, if the test output is not found
+						// we generate a new one with the current test output
+						std::fs::write(&new_file_path, &output).expect("writing new test output");
+					}
+					test_succeeded = false;
+				} else {
+					// this is not an expected failure mode, so we just panic
+					panic!("failed to read output file for {}: {}", test_name, err);
+				}
+
+				// there is no expected output in this case, since the valid
+				// file was not found
+				None
+			}
+		};
+
+		let test_blessed = test_blessed || (bless && !test_succeeded);
+		if test_blessed {
+			std::fs::write(expected_file_path, &output).expect("writing blessed test output");
+			if unexpected.is_some() {
+				// the expectation changed between success and error
+				std::fs::remove_file(other_file_path).expect("removing outdated test output");
+			}
+		}
+
+		if test_succeeded || test_blessed {
+			// any `.valid.new` or `.error.new` file is stale once the test passes
+			for stale_file_path in [
+				new_file_for(&valid_file_path),
+				new_file_for(&error_file_path),
+			] {
+				if let Err(err) = std::fs::remove_file(&stale_file_path) {
+					if err.kind() != ErrorKind::NotFound {
+						panic!("failed to remove stale output for {}: {}", test_name, err);
+					}
+				}
+			}
+		}
+
+		TestDataResultItem {
+			success: test_succeeded || test_blessed,
+			blessed: test_blessed,
+			name: test_name,
+			valid_file: expected_file_name.into(),
+			expect: expected.map(|x| format.display_lines(&x)),
+			actual: format.display_lines(&output),
+			panic: None,
+			unexpected: if test_blessed {
+				None
+			} else {
+				unexpected.or(invalid_placeholder)
+			},
+			skipped: None,
+			expected_failure: false,
+			files: Vec::new(),
+			duration: Duration::ZERO,
+		}
+	}
+
+	/// Applies the line normalization to the valid file contents, unless
+	/// the comparison is exact.
+	fn normalize_expected(&self, format: Format, expected: Vec<u8>) -> Vec<u8> {
+		match format {
+			Format::Lines => {
+				let expected = String::from_utf8_lossy(&expected).into_owned();
+				self.read_lines(expected).join("\n").into_bytes()
+			}
+			Format::Raw | Format::Bytes => expected,
+		}
+	}
+
+	/// Applies the redactions to the test output, unless it is binary.
+	pub(super) fn redact_output(&self, format: Format, output: Vec<u8>) -> Vec<u8> {
+		if format == Format::Bytes {
+			return output;
+		}
+		match String::from_utf8(output) {
+			Ok(text) => self.redact_text(text).into_bytes(),
+			Err(err) => err.into_bytes(),
+		}
+	}
+
+	/// Applies the redactions to the text, if any.
+	pub(super) fn redact_text(&self, text: String) -> String {
+		#[cfg(feature = "redact")]
+		let text = match &self.redactions {
+			Some(redactions) => redactions.apply(&text),
+			None => crate::redact::apply_current(text),
+		};
+		text
+	}
+
+	pub(super) fn read_lines(&self, text: String) -> Vec<String> {
+		if self.trim {
+			crate::text::lines(text)
+		} else {
+			crate::text::split_lines(text)
+		}
+	}
+
+	/// Returns the valid file path for the input by replacing the input
+	/// extension with the valid extension.
+	pub(super) fn valid_file_for(&self, input_path: &Path) -> PathBuf {
+		self.file_with_extension(input_path, &self.valid_extension)
+	}
+
+	/// Returns the error file path for the input by replacing the input
+	/// extension with the error extension.
+	fn error_file_for(&self, input_path: &Path) -> PathBuf {
+		self.file_with_extension(input_path, &self.error_extension)
+	}
+
+	pub(super) fn file_with_extension(&self, input_path: &Path, extension: &str) -> PathBuf {
+		let input_name = input_path.file_name().unwrap().to_string_lossy();
+		let input_suffix = format!(".{}", self.input_extension);
+		let base_name = input_name.strip_suffix(&input_suffix).unwrap();
+		input_path.with_file_name(format!("{}.{}", base_name, extension))
+	}
+}
+
+/// Applies an `xfail` directive to the test result: a failure is expected,
+/// and passing is a failure.
+pub(super) fn expect_fail_result(
+	case: &TestCase,
+	mut item: TestDataResultItem,
+) -> TestDataResultItem {
+	if case.expect_fail() {
+		if !item.success {
+			item.success = true;
+			item.expected_failure = true;
+		} else if !item.blessed {
+			item.success = false;
+			item.unexpected = Some("passed, but is marked as expected to fail".into());
+		}
+	}
+	item
+}
+
+/// Returns the path for a new valid file generated from the test output.
+pub(super) fn new_file_for(valid_file_path: &Path) -> PathBuf {
+	let valid_name = valid_file_path.file_name().unwrap().to_string_lossy();
+	valid_file_path.with_file_name(format!("{}.{}", valid_name, TEST_NEW_FILE_EXTENSION))
+}
+
+/// Outputs the summary for the test results and panics if any test failed.
+pub(super) fn output_result(result: &TestDataResult) {
+	write_reports_from_env(result);
+
+	for it in result.tests.iter() {
+		if it.blessed {
+			println!("blessed: {}", it.name);
+		} else if it.skipped.is_some() {
+			println!("skipped: {}", it.name);
+		} else if it.expected_failure {
+			println!("expected failure: {}", it.name);
+		} else if it.success {
+			println!("passed: {}", it.name);
+		} else {
+			println!("failed: {}", it.name);
+		}
+	}
+
+	if !result.skipped.is_empty() {
+		let count = result.skipped.len();
+		println!(
+			"skipped: {} test case{} not matching filter `{}`",
+			count,
+			if count != 1 { "s" } else { "" },
+			result.filter.as_deref().unwrap_or_default(),
+		);
+	}
+
+	if !result.warnings.is_empty() {
+		eprintln!("\n===== Warnings =====\n");
+		for it in result.warnings.iter() {
+			eprintln!("- {}", it);
+		}
+		eprintln!();
+	}
+
+	if result.tests.iter().any(|x| x.skipped.is_some()) {
+		eprintln!("\n===== Skipped tests =====\n");
+		for it in result.tests.iter() {
+			match it.skipped.as_deref() {
+				Some("") => eprintln!("- {}", it.name),
+				Some(reason) => eprintln!("- {} ({})", it.name, reason),
+				None => {}
+			}
+		}
+		eprintln!();
+	}
+
+	if result.tests.iter().any(|x| x.expected_failure) {
+		eprintln!("\n===== Expected failures =====\n");
+		for it in result.tests.iter() {
+			if it.expected_failure {
+				eprintln!("- {}", it.name);
+			}
+		}
+		eprintln!();
+	}
+
+	if result.tests.iter().any(|x| x.blessed) {
+		eprintln!("\n===== Updated valid files =====\n");
+		for it in result.tests.iter() {
+			if it.blessed {
+				eprintln!("- {} (test {})", it.valid_file, it.name);
+			}
+		}
+		eprintln!();
+	}
+
+	if !result.success() {
+		let mut failed_count = 0;
+
+		for it in result.tests.iter() {
+			if !it.success {
+				failed_count += 1;
+
+				if let Some(panic) = &it.panic {
+					eprintln!("\n=> `{}` panicked: {}", it.name, panic);
+				} else if let Some(unexpected) = &it.unexpected {
+					let diff = it.diff_with_color(crate::diff::use_color_for_stderr());
+					if let Some(diff) = diff {
+						eprintln!("\n=> `{}` {}:", it.name, unexpected);
+						eprintln!("\n{}", diff);
+					} else {
+						eprintln!("\n=> `{}` {}:\n", it.name, unexpected);
+						for line in it.actual.iter() {
+							eprintln!("    {}", line);
+						}
+					}
+				} else if let Some(diff) = it.diff_with_color(crate::diff::use_color_for_stderr()) {
+					eprintln!(
+						"\n=> `{}` output did not match `{}`:",
+						it.name, it.valid_file
+					);
+					eprintln!("\n{}", diff);
+				} else {
+					eprintln!("\n=> `{}` for test `{}` not found", it.valid_file, it.name);
+					eprintln!(
+						".. created `{}.new` with the current test output",
+						it.valid_file
+					);
+				}
+			}
+		}
+
+		eprintln!("\n===== Failed tests =====\n");
+		for it in result.tests.iter() {
+			if it.panic.is_some() {
+				eprintln!("- {} (panicked)", it.name);
+			} else if !it.success {
+				eprintln!("- {}", it.name);
+			}
+		}
+		eprintln!();
+
+		panic!(
+			"{} test case{} failed",
+			failed_count,
+			if failed_count != 1 { "s" } else { "" }
+		);
+	}
+}
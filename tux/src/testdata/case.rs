@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+const DIRECTIVE_PREFIX: &str = "// tux:";
+
+/// Information about a single test case, including the directives from the
+/// header of the `.input` file.
+///
+/// Directives are lines at the start of the input file in the format
+/// `// tux: directive`. They are removed from the input before it is passed
+/// to the test callback. The supported directives are:
+///
+/// - `skip` or `skip=reason`: the test case is not run.
+/// - `xfail`: the test case is expected to fail. It is reported as an
+///   expected failure if it fails, and fails if it passes.
+/// - `args=ARGS`: whitespace separated arguments for the test callback (see
+///   [`TestCase::args`]). Multiple `args` directives are concatenated.
+/// - `timeout=DURATION`: a timeout for the test case (see [`TestCase::timeout`]).
+///
+/// ```text
+/// // tux: args=--strict
+/// // tux: timeout=500ms
+/// if (a) {}
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TestCase {
+	name: String,
+	skip: Option<String>,
+	expect_fail: bool,
+	args: Vec<String>,
+	timeout: Option<Duration>,
+}
+
+impl TestCase {
+	/// The test case name. This is the input path relative to the test
+	/// directory, using `/` as separator.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Returns `true` if the case has a `skip` directive.
+	pub fn skip(&self) -> bool {
+		self.skip.is_some()
+	}
+
+	/// Reason given in a `skip=reason` directive, if any.
+	pub fn skip_reason(&self) -> Option<&str> {
+		self.skip.as_deref().filter(|x| !x.is_empty())
+	}
+
+	/// Returns `true` if the case has an `xfail` directive.
+	pub fn expect_fail(&self) -> bool {
+		self.expect_fail
+	}
+
+	/// Arguments from the `args` directives.
+	pub fn args(&self) -> &[String] {
+		&self.args
+	}
+
+	/// Timeout from the `timeout` directive. The duration is given in
+	/// seconds (e.g. `5` or `1.5s`), milliseconds (`500ms`), or minutes
+	/// (`2m`).
+	///
+	/// This overrides the default timeout set with [`TestData::timeout`](super::TestData::timeout).
+	/// This is only a hard timeout with [`TestData::run_isolated`](super::TestData::run_isolated)
+	/// and [`TestData::run_bin`](super::TestData::run_bin). With the other run
+	/// methods, a test case that never returns hangs the run.
+	pub fn timeout(&self) -> Option<Duration> {
+		self.timeout
+	}
+}
+
+/// Parses the directives from the start of the input text, returning the
+/// test case and the remaining input.
+pub(super) fn parse_test_case<'a>(
+	name: &str,
+	input: &'a str,
+) -> Result<(TestCase, &'a str), String> {
+	let mut case = TestCase {
+		name: name.to_string(),
+		..Default::default()
+	};
+
+	let mut remaining = input;
+	while let Some(directive) = remaining
+		.trim_start_matches([' ', '\t'])
+		.strip_prefix(DIRECTIVE_PREFIX)
+	{
+		let (line, next) = directive.split_once('\n').unwrap_or((directive, ""));
+		remaining = next;
+
+		let line = line.trim();
+		let (directive, value) = match line.split_once('=') {
+			Some((directive, value)) => (directive.trim(), Some(value.trim())),
+			None => (line, None),
+		};
+		match (directive, value) {
+			("skip", reason) => case.skip = Some(reason.unwrap_or_default().to_string()),
+			("xfail", None) => case.expect_fail = true,
+			("args", Some(args)) => case
+				.args
+				.extend(args.split_whitespace().map(|x| x.to_string())),
+			("timeout", Some(timeout)) => {
+				let timeout = parse_duration(timeout)
+					.ok_or_else(|| format!("invalid timeout in directive `{}`", line))?;
+				case.timeout = Some(timeout);
+			}
+			_ => return Err(format!("invalid directive `{}`", line)),
+		}
+	}
+
+	Ok((case, remaining))
+}
+
+fn parse_duration(text: &str) -> Option<Duration> {
+	let (number, scale) = if let Some(number) = text.strip_suffix("ms") {
+		(number, 0.001)
+	} else if let Some(number) = text.strip_suffix('s') {
+		(number, 1.0)
+	} else if let Some(number) = text.strip_suffix('m') {
+		(number, 60.0)
+	} else {
+		(text, 1.0)
+	};
+	let number = number.trim().parse::<f64>().ok()?;
+	Duration::try_from_secs_f64(number * scale).ok()
+}
+
+#[cfg(test)]
+mod test_case {
+	use std::time::Duration;
+
+	use super::parse_test_case;
+
+	#[test]
+	fn input_without_directives() {
+		let (case, input) = parse_test_case("a.input", "line 1\n// tux: skip").unwrap();
+		assert_eq!(case.name(), "a.input");
+		assert!(!case.skip());
+		assert!(!case.expect_fail());
+		assert_eq!(input, "line 1\n// tux: skip");
+	}
+
+	#[test]
+	fn parses_and_removes_directives() {
+		let input = "// tux: xfail\n  // tux: args=--strict  -v\r\n// tux: args=x\n// tux: timeout=500ms\nline 1\nline 2";
+		let (case, input) = parse_test_case("a.input", input).unwrap();
+		assert!(case.expect_fail());
+		assert_eq!(case.args(), ["--strict", "-v", "x"]);
+		assert_eq!(case.timeout(), Some(Duration::from_millis(500)));
+		assert_eq!(input, "line 1\nline 2");
+	}
+
+	#[test]
+	fn parses_skip_with_reason() {
+		let (case, input) = parse_test_case("a.input", "// tux: skip").unwrap();
+		assert!(case.skip());
+		assert_eq!(case.skip_reason(), None);
+		assert_eq!(input, "");
+
+		let (case, _) = parse_test_case("a.input", "// tux: skip = not supported").unwrap();
+		assert!(case.skip());
+		assert_eq!(case.skip_reason(), Some("not supported"));
+	}
+
+	#[test]
+	fn parses_timeout_units() {
+		let timeout = |text: &str| {
+			let input = format!("// tux: timeout={}", text);
+			parse_test_case("a.input", &input)
+				.unwrap()
+				.0
+				.timeout()
+				.unwrap()
+		};
+		assert_eq!(timeout("2"), Duration::from_secs(2));
+		assert_eq!(timeout("1.5s"), Duration::from_millis(1500));
+		assert_eq!(timeout("2m"), Duration::from_secs(120));
+	}
+
+	#[test]
+	fn fails_for_invalid_directives() {
+		let err = parse_test_case("a.input", "// tux: skp").unwrap_err();
+		assert_eq!(err, "invalid directive `skp`");
+
+		let err = parse_test_case("a.input", "// tux: timeout=soon").unwrap_err();
+		assert_eq!(err, "invalid timeout in directive `timeout=soon`");
+
+		assert!(parse_test_case("a.input", "// tux: xfail=1").is_err());
+		assert!(parse_test_case("a.input", "// tux: args").is_err());
+	}
+}
@@ -0,0 +1,114 @@
+use std::{
+	any::Any,
+	cell::{Cell, RefCell},
+	marker::PhantomData,
+	panic::{catch_unwind, AssertUnwindSafe},
+	sync::Once,
+};
+
+thread_local! {
+	static LAST_PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+	static SUPPRESS_PANIC_OUTPUT: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Restores the panic output for the current thread when dropped. See
+/// [`suppress_panic_output`].
+pub(super) struct PanicOutputGuard {
+	// the guard must be dropped in the thread it was created
+	_thread: PhantomData<*const ()>,
+}
+
+impl Drop for PanicOutputGuard {
+	fn drop(&mut self) {
+		SUPPRESS_PANIC_OUTPUT.with(|x| x.set(x.get() - 1));
+	}
+}
+
+/// Suppresses the default panic output for the current thread while the
+/// returned guard is alive. This is similar to [`assert_panic!`](crate::assert_panic),
+/// except that the location of the panic is recorded for [`catch_panic`].
+///
+/// The panic hook is global, so instead of replacing it for each run, a
+/// single hook is installed on the first call, which forwards to the previous
+/// hook for the threads that are not suppressed. This allows test runs and
+/// other tests to panic in parallel.
+pub(super) fn suppress_panic_output() -> PanicOutputGuard {
+	install_panic_hook();
+	SUPPRESS_PANIC_OUTPUT.with(|x| x.set(x.get() + 1));
+	PanicOutputGuard {
+		_thread: PhantomData,
+	}
+}
+
+fn is_panic_output_suppressed() -> bool {
+	SUPPRESS_PANIC_OUTPUT.with(|x| x.get() > 0)
+}
+
+fn install_panic_hook() {
+	static INSTALL: Once = Once::new();
+	INSTALL.call_once(|| {
+		let prev_hook = std::panic::take_hook();
+		std::panic::set_hook(Box::new(move |info| {
+			let location = info.location().map(|x| x.to_string());
+			LAST_PANIC_LOCATION.with(|x| *x.borrow_mut() = location);
+			if !is_panic_output_suppressed() {
+				prev_hook(info);
+			}
+		}));
+	});
+}
+
+/// Runs the function returning the panic message in case it panics.
+pub(super) fn catch_panic<T, F: FnOnce() -> T>(f: F) -> Result<T, String> {
+	LAST_PANIC_LOCATION.with(|x| x.borrow_mut().take());
+	catch_unwind(AssertUnwindSafe(f)).map_err(|err| {
+		let message = panic_message(err.as_ref());
+		match LAST_PANIC_LOCATION.with(|x| x.borrow_mut().take()) {
+			Some(location) => format!("{} (at {})", message, location),
+			None => message,
+		}
+	})
+}
+
+fn panic_message(err: &(dyn Any + Send)) -> String {
+	if let Some(message) = err.downcast_ref::<&str>() {
+		message.to_string()
+	} else if let Some(message) = err.downcast_ref::<String>() {
+		message.clone()
+	} else {
+		"panic with a non-string payload".into()
+	}
+}
+
+#[cfg(test)]
+mod test_catch {
+	use super::*;
+
+	#[test]
+	fn suppresses_panic_output_for_current_thread_only() {
+		let guard = suppress_panic_output();
+		assert!(is_panic_output_suppressed());
+		let other_thread = std::thread::spawn(is_panic_output_suppressed);
+		assert!(!other_thread.join().unwrap());
+		drop(guard);
+		assert!(!is_panic_output_suppressed());
+	}
+
+	#[test]
+	fn restores_panic_output_after_overlapping_guards() {
+		let first = suppress_panic_output();
+		let second = suppress_panic_output();
+		drop(first);
+		assert!(is_panic_output_suppressed());
+		drop(second);
+		assert!(!is_panic_output_suppressed());
+	}
+
+	#[test]
+	fn records_panic_location() {
+		let _guard = suppress_panic_output();
+		let err = catch_panic(|| panic!("some panic")).unwrap_err();
+		assert!(err.starts_with("some panic (at "), "unexpected: {}", err);
+		assert!(err.contains("catch.rs"), "unexpected: {}", err);
+	}
+}
@@ -0,0 +1,450 @@
+use std::path::Path;
+
+use super::{Format, TestData, TestDataResultItem, TextPattern, TEST_CHECK_FILE_EXTENSION};
+
+const CHECK_DIRECTIVE_PREFIX: &str = "CHECK";
+const COMMENT_MARKERS: [&str; 4] = ["//", "#", ";", "--"];
+
+/// Kind of a check directive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CheckKind {
+	/// `CHECK:` matches anywhere after the previous match.
+	Check,
+	/// `CHECK-NEXT:` matches in the line after the previous match.
+	Next,
+	/// `CHECK-SAME:` matches in the same line as the previous match.
+	Same,
+	/// `CHECK-NOT:` must not match between the previous and the next match.
+	Not,
+}
+
+impl CheckKind {
+	fn parse(name: &str) -> Option<CheckKind> {
+		let kind = match name.strip_prefix(CHECK_DIRECTIVE_PREFIX)? {
+			"" => CheckKind::Check,
+			"-NEXT" => CheckKind::Next,
+			"-SAME" => CheckKind::Same,
+			"-NOT" => CheckKind::Not,
+			_ => return None,
+		};
+		Some(kind)
+	}
+
+	fn name(&self) -> &'static str {
+		match self {
+			CheckKind::Check => "CHECK",
+			CheckKind::Next => "CHECK-NEXT",
+			CheckKind::Same => "CHECK-SAME",
+			CheckKind::Not => "CHECK-NOT",
+		}
+	}
+}
+
+/// A single check directive, with the line where it was declared.
+#[derive(Debug)]
+struct CheckDirective {
+	kind: CheckKind,
+	text: String,
+	pattern: TextPattern,
+	line: usize,
+}
+
+/// Position in the output, as a line index and a byte offset in the line.
+type Position = (usize, usize);
+
+/// List of check directives from a `.check` file or from the comments in an
+/// `.input` file.
+#[derive(Debug)]
+pub(super) struct CheckList {
+	source: String,
+	directives: Vec<CheckDirective>,
+}
+
+impl CheckList {
+	/// Parses the check directives from the text. The source is the file name
+	/// used in failure messages.
+	///
+	/// Returns `None` if the text has no directives.
+	pub fn parse(source: &str, text: &str) -> Result<Option<CheckList>, String> {
+		let mut directives = Vec::new();
+		for (index, line) in text.lines().enumerate() {
+			let line_number = index + 1;
+			let directive = parse_directive(line)
+				.map_err(|err| format!("{} at `{}:{}`", err, source, line_number))?;
+			let Some((kind, pattern)) = directive else {
+				continue;
+			};
+
+			let error = |msg: String| {
+				format!(
+					"invalid `{}` directive at `{}:{}`: {}",
+					kind.name(),
+					source,
+					line_number,
+					msg
+				)
+			};
+
+			let pattern = pattern.trim();
+			if pattern.is_empty() {
+				return Err(error("empty pattern".into()));
+			}
+
+			let has_previous_match = directives
+				.iter()
+				.any(|x: &CheckDirective| x.kind != CheckKind::Not);
+			if (kind == CheckKind::Next || kind == CheckKind::Same) && !has_previous_match {
+				return Err(error("there is no previous match".into()));
+			}
+
+			directives.push(CheckDirective {
+				kind,
+				text: pattern.to_string(),
+				pattern: TextPattern::new(pattern).map_err(error)?,
+				line: line_number,
+			});
+		}
+
+		if directives.is_empty() {
+			Ok(None)
+		} else {
+			Ok(Some(CheckList {
+				source: source.to_string(),
+				directives,
+			}))
+		}
+	}
+
+	/// Checks the directives against the output lines, in order. Returns an
+	/// error describing the failed directive and where it was looked for.
+	pub fn check<S: AsRef<str>>(&self, output: &[S]) -> Result<(), String> {
+		let mut position: Option<Position> = None;
+		let mut pending_not = Vec::new();
+		for it in self.directives.iter() {
+			let start = position.unwrap_or((0, 0));
+			let found = match it.kind {
+				CheckKind::Not => {
+					pending_not.push(it);
+					continue;
+				}
+				CheckKind::Check => {
+					let found = find_from(output, start, &it.pattern);
+					found.ok_or_else(|| match position {
+						Some((line, _)) => {
+							self.failure(it, format!("not found after output line {}", line + 1))
+						}
+						None => self.failure(it, "not found in the output".into()),
+					})?
+				}
+				CheckKind::Next => {
+					let line = start.0 + 1;
+					let found = output.get(line).and_then(|text| {
+						let range = it.pattern.find(text.as_ref())?;
+						Some(((line, range.start), (line, range.end)))
+					});
+					found.ok_or_else(|| {
+						if line < output.len() {
+							self.failure(it, format!("did not match output line {}", line + 1))
+						} else {
+							self.failure(it, format!("did not match, output ends at line {}", line))
+						}
+					})?
+				}
+				CheckKind::Same => {
+					let (line, offset) = start;
+					let text = output[line].as_ref();
+					let found = it
+						.pattern
+						.find(&text[offset..])
+						.map(|range| ((line, offset + range.start), (line, offset + range.end)));
+					found.ok_or_else(|| {
+						self.failure(
+							it,
+							format!("did not match the rest of output line {}", line + 1),
+						)
+					})?
+				}
+			};
+
+			let (match_start, match_end) = found;
+			self.check_not(output, &pending_not, start, match_start)?;
+			pending_not.clear();
+			position = Some(match_end);
+		}
+
+		let start = position.unwrap_or((0, 0));
+		self.check_not(output, &pending_not, start, (output.len(), 0))
+	}
+
+	/// Checks that none of the `CHECK-NOT` directives match between the given
+	/// positions.
+	fn check_not<S: AsRef<str>>(
+		&self,
+		output: &[S],
+		directives: &[&CheckDirective],
+		start: Position,
+		end: Position,
+	) -> Result<(), String> {
+		for line in start.0..=end.0 {
+			let Some(text) = output.get(line).map(|x| x.as_ref()) else {
+				break;
+			};
+			let from = if line == start.0 { start.1 } else { 0 };
+			let to = if line == end.0 { end.1 } else { text.len() };
+			if from >= to {
+				continue;
+			}
+			for it in directives.iter() {
+				if it.pattern.find(&text[from..to]).is_some() {
+					return Err(self.failure(it, format!("matched output line {}", line + 1)));
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn failure(&self, directive: &CheckDirective, message: String) -> String {
+		format!(
+			"failed `{}: {}` at `{}:{}`, {}",
+			directive.kind.name(),
+			directive.text,
+			self.source,
+			directive.line,
+			message
+		)
+	}
+}
+
+/// Finds the first match for the pattern at or after the given position,
+/// returning the start and end of the match.
+fn find_from<S: AsRef<str>>(
+	output: &[S],
+	start: Position,
+	pattern: &TextPattern,
+) -> Option<(Position, Position)> {
+	for (line, text) in output.iter().enumerate().skip(start.0) {
+		let offset = if line == start.0 { start.1 } else { 0 };
+		if let Some(range) = pattern.find(&text.as_ref()[offset..]) {
+			return Some(((line, offset + range.start), (line, offset + range.end)));
+		}
+	}
+	None
+}
+
+/// Parses a directive line, which may start with a comment marker. Returns
+/// `None` if the line is not a directive.
+fn parse_directive(line: &str) -> Result<Option<(CheckKind, &str)>, String> {
+	let mut text = line.trim_start();
+	for marker in COMMENT_MARKERS {
+		if let Some(rest) = text.strip_prefix(marker) {
+			text = rest.trim_start();
+			break;
+		}
+	}
+
+	// a directive is `CHECK:` or `CHECK-NAME:`, other words are not directives
+	match text.strip_prefix(CHECK_DIRECTIVE_PREFIX) {
+		Some(rest) if rest.starts_with([':', '-']) => {}
+		_ => return Ok(None),
+	}
+
+	let name_len = text
+		.find(|c: char| !(c.is_ascii_uppercase() || c == '-'))
+		.unwrap_or(text.len());
+	let (name, rest) = text.split_at(name_len);
+	let Some(pattern) = rest.strip_prefix(':') else {
+		return Ok(None);
+	};
+
+	match CheckKind::parse(name) {
+		Some(kind) => Ok(Some((kind, pattern))),
+		None => Err(format!("unknown check directive `{}`", name)),
+	}
+}
+
+impl TestData {
+	/// Returns the check directives for the test case, from the `.check` file
+	/// alongside the input or, if enabled with [`TestData::input_checks`],
+	/// from the comments in the input itself.
+	///
+	/// Returns `None` if there is a `.valid` file, which is always compared
+	/// with the output. A `.check` file alongside a `.valid` file is an error.
+	pub(super) fn checks_for(
+		&self,
+		input_path: &Path,
+		input: &str,
+	) -> Result<Option<CheckList>, String> {
+		let check_file_path = self.file_with_extension(input_path, TEST_CHECK_FILE_EXTENSION);
+		let check_file_name = check_file_path.file_name().unwrap().to_string_lossy();
+		let valid_file_path = self.valid_file_for(input_path);
+		if valid_file_path.is_file() {
+			if check_file_path.is_file() {
+				let valid_file_name = valid_file_path.file_name().unwrap().to_string_lossy();
+				return Err(format!(
+					"has both `{}` and `{}`, only one is expected",
+					valid_file_name, check_file_name
+				));
+			}
+			return Ok(None);
+		}
+
+		match std::fs::read_to_string(&check_file_path) {
+			Ok(text) => match CheckList::parse(&check_file_name, &text)? {
+				Some(checks) => Ok(Some(checks)),
+				None => Err(format!("has no check directives in `{}`", check_file_name)),
+			},
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+				if !self.input_checks {
+					return Ok(None);
+				}
+				let input_name = input_path.file_name().unwrap().to_string_lossy();
+				CheckList::parse(&input_name, input)
+			}
+			Err(err) => panic!("failed to read check file `{}`: {}", check_file_name, err),
+		}
+	}
+
+	/// Checks the outcome of the callback for a test case with the check
+	/// directives, instead of comparing it with the valid file. The outcome
+	/// is an error with the panic message if the callback panicked.
+	pub(super) fn run_case_checks(
+		&self,
+		test_name: String,
+		checks: &CheckList,
+		outcome: Result<Result<Vec<u8>, String>, String>,
+	) -> TestDataResultItem {
+		let mut item = TestDataResultItem::new(test_name, checks.source.clone());
+		match outcome {
+			Ok(Ok(output)) => {
+				let output = self.redact_output(Format::Lines, output);
+				let output = Format::Lines.display_lines(&output);
+				match checks.check(&output) {
+					Ok(()) => item.success = true,
+					Err(err) => item.unexpected = Some(err),
+				}
+				item.actual = output;
+			}
+			Ok(Err(error)) => {
+				item.unexpected = Some("failed with an error".into());
+				item.actual = self.read_lines(self.redact_text(error));
+			}
+			Err(panic) => {
+				item.panic = Some(panic);
+			}
+		}
+		item
+	}
+}
+
+#[cfg(test)]
+mod test_check {
+	use super::CheckList;
+
+	fn check(checks: &str, output: &[&str]) -> Result<(), String> {
+		let checks = CheckList::parse("a.check", checks).unwrap().unwrap();
+		checks.check(output)
+	}
+
+	#[test]
+	fn parses_directives_from_comments() {
+		let text = "fn main() {}\n// CHECK: a\n# CHECK-NEXT: b\n  ; CHECK-NOT: c\n-- CHECK-SAME: d\nCHECKED: x";
+		let checks = CheckList::parse("a.input", text).unwrap().unwrap();
+		let kinds = checks
+			.directives
+			.iter()
+			.map(|x| (x.kind.name(), x.text.as_str(), x.line))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			kinds,
+			[
+				("CHECK", "a", 2),
+				("CHECK-NEXT", "b", 3),
+				("CHECK-NOT", "c", 4),
+				("CHECK-SAME", "d", 5),
+			]
+		);
+	}
+
+	#[test]
+	fn returns_none_without_directives() {
+		assert!(CheckList::parse("a.input", "some input").unwrap().is_none());
+	}
+
+	#[test]
+	fn reports_invalid_directives() {
+		let err = CheckList::parse("a.check", "CHECK-FOO: a").unwrap_err();
+		assert_eq!(err, "unknown check directive `CHECK-FOO` at `a.check:1`");
+
+		let err = CheckList::parse("a.check", "CHECK-NEXT: a").unwrap_err();
+		assert_eq!(
+			err,
+			"invalid `CHECK-NEXT` directive at `a.check:1`: there is no previous match"
+		);
+
+		let err = CheckList::parse("a.check", "CHECK: a\nCHECK:").unwrap_err();
+		assert_eq!(
+			err,
+			"invalid `CHECK` directive at `a.check:2`: empty pattern"
+		);
+	}
+
+	#[test]
+	fn check_matches_in_order() {
+		let output = ["one", "two", "three"];
+		assert!(check("CHECK: one\nCHECK: three", &output).is_ok());
+		assert!(check("CHECK: tw\nCHECK: ee", &output).is_ok());
+		assert_eq!(
+			check("CHECK: three\nCHECK: two", &output).unwrap_err(),
+			"failed `CHECK: two` at `a.check:2`, not found after output line 3"
+		);
+		assert_eq!(
+			check("CHECK: four", &output).unwrap_err(),
+			"failed `CHECK: four` at `a.check:1`, not found in the output"
+		);
+	}
+
+	#[test]
+	fn check_next_matches_following_line() {
+		let output = ["one", "two", "three"];
+		assert!(check("CHECK: one\nCHECK-NEXT: two", &output).is_ok());
+		assert_eq!(
+			check("CHECK: one\nCHECK-NEXT: three", &output).unwrap_err(),
+			"failed `CHECK-NEXT: three` at `a.check:2`, did not match output line 2"
+		);
+		assert_eq!(
+			check("CHECK: three\nCHECK-NEXT: four", &output).unwrap_err(),
+			"failed `CHECK-NEXT: four` at `a.check:2`, did not match, output ends at line 3"
+		);
+	}
+
+	#[test]
+	fn check_same_matches_rest_of_line() {
+		let output = ["a = 1 + 2"];
+		assert!(check("CHECK: a\nCHECK-SAME: 1\nCHECK-SAME: 2", &output).is_ok());
+		assert_eq!(
+			check("CHECK: 2\nCHECK-SAME: 1", &output).unwrap_err(),
+			"failed `CHECK-SAME: 1` at `a.check:2`, did not match the rest of output line 1"
+		);
+	}
+
+	#[test]
+	fn check_not_fails_between_matches() {
+		let output = ["start", "warning", "end", "error"];
+		assert!(check("CHECK: start\nCHECK-NOT: error\nCHECK: end", &output).is_ok());
+		assert_eq!(
+			check("CHECK: start\nCHECK-NOT: warn\nCHECK: end", &output).unwrap_err(),
+			"failed `CHECK-NOT: warn` at `a.check:2`, matched output line 2"
+		);
+		assert_eq!(
+			check("CHECK: end\nCHECK-NOT: error", &output).unwrap_err(),
+			"failed `CHECK-NOT: error` at `a.check:2`, matched output line 4"
+		);
+		assert!(check("CHECK-NOT: fatal", &output).is_ok());
+	}
+
+	#[test]
+	fn check_supports_placeholders() {
+		let output = ["took 12ms", "id 0a1b"];
+		assert!(check("CHECK: took {{re:\\d+}}ms\nCHECK-NEXT: id {{*}}", &output).is_ok());
+	}
+}
@@ -0,0 +1,135 @@
+use std::{
+	collections::VecDeque,
+	path::{Path, PathBuf},
+};
+
+/// Returns the path and name of all files in the given directory (recursively)
+/// with the given extension, sorted by name.
+///
+/// The test name is the file path relative to the root directory, using `/`
+/// as separator.
+pub(super) fn collect_test_inputs_with_name(
+	root_path: &Path,
+	input_extension: &str,
+) -> Vec<(PathBuf, String)> {
+	let mut test_inputs_with_name = Vec::new();
+
+	let input_suffix = format!(".{}", input_extension);
+
+	let mut dirs_to_scan_with_name = VecDeque::new();
+	dirs_to_scan_with_name.push_back((root_path.to_owned(), String::new()));
+
+	while let Some((current_dir, current_name)) = dirs_to_scan_with_name.pop_front() {
+		let entries = std::fs::read_dir(&current_dir).expect("reading test directory");
+		let entries = entries.map(|x| x.expect("reading test directory entry"));
+
+		// the order here is important to keep the sort order for tests
+		let mut entries = entries.collect::<Vec<_>>();
+		entries.sort_by_key(|x| x.file_name());
+
+		for entry in entries {
+			let entry_path = entry.path();
+			let file_name = entry.file_name().to_string_lossy().to_string();
+			let entry_name = if !current_name.is_empty() {
+				format!("{}/{}", current_name, file_name)
+			} else {
+				file_name.clone()
+			};
+
+			let entry_info =
+				std::fs::metadata(&entry_path).expect("reading test directory metadata");
+			if entry_info.is_dir() {
+				dirs_to_scan_with_name.push_back((entry_path, entry_name));
+			} else if file_name.len() > input_suffix.len() && file_name.ends_with(&input_suffix) {
+				test_inputs_with_name.push((entry_path, entry_name));
+			}
+		}
+	}
+
+	test_inputs_with_name
+}
+
+/// Returns the path and name of all test case directories in the given
+/// directory (recursively), sorted by name. A test case directory is one
+/// containing a subdirectory with the given input name.
+///
+/// Test case directories are not scanned for nested test cases.
+#[cfg(feature = "temp")]
+pub(super) fn collect_test_dirs_with_name(
+	root_path: &Path,
+	input_dir_name: &str,
+) -> Vec<(PathBuf, String)> {
+	let mut test_dirs_with_name = Vec::new();
+
+	let mut dirs_to_scan_with_name = VecDeque::new();
+	dirs_to_scan_with_name.push_back((root_path.to_owned(), String::new()));
+
+	while let Some((current_dir, current_name)) = dirs_to_scan_with_name.pop_front() {
+		let entries = std::fs::read_dir(&current_dir).expect("reading test directory");
+		let entries = entries.map(|x| x.expect("reading test directory entry"));
+
+		let mut entries = entries.collect::<Vec<_>>();
+		entries.sort_by_key(|x| x.file_name());
+
+		for entry in entries {
+			let entry_path = entry.path();
+			if !entry_path.is_dir() {
+				continue;
+			}
+
+			let file_name = entry.file_name().to_string_lossy().to_string();
+			let entry_name = if !current_name.is_empty() {
+				format!("{}/{}", current_name, file_name)
+			} else {
+				file_name
+			};
+
+			if entry_path.join(input_dir_name).is_dir() {
+				test_dirs_with_name.push((entry_path, entry_name));
+			} else {
+				dirs_to_scan_with_name.push_back((entry_path, entry_name));
+			}
+		}
+	}
+
+	// the scan is breadth first, so nested cases need sorting by name
+	test_dirs_with_name.sort_by(|a, b| a.1.cmp(&b.1));
+	test_dirs_with_name
+}
+
+/// Returns the path and name of all files in the given directory
+/// (recursively), sorted by name. The name is the file path relative to the
+/// root directory, using `/` as separator.
+///
+/// Returns an empty list if the directory does not exist.
+pub(super) fn collect_files_with_name(root_path: &Path) -> Vec<(PathBuf, String)> {
+	let mut files_with_name = Vec::new();
+	if !root_path.is_dir() {
+		return files_with_name;
+	}
+
+	let mut dirs_to_scan_with_name = VecDeque::new();
+	dirs_to_scan_with_name.push_back((root_path.to_owned(), String::new()));
+
+	while let Some((current_dir, current_name)) = dirs_to_scan_with_name.pop_front() {
+		let entries = std::fs::read_dir(&current_dir).expect("reading directory");
+		for entry in entries.map(|x| x.expect("reading directory entry")) {
+			let entry_path = entry.path();
+			let file_name = entry.file_name().to_string_lossy().to_string();
+			let entry_name = if !current_name.is_empty() {
+				format!("{}/{}", current_name, file_name)
+			} else {
+				file_name
+			};
+
+			if entry_path.is_dir() {
+				dirs_to_scan_with_name.push_back((entry_path, entry_name));
+			} else {
+				files_with_name.push((entry_path, entry_name));
+			}
+		}
+	}
+
+	files_with_name.sort_by(|a, b| a.1.cmp(&b.1));
+	files_with_name
+}
@@ -0,0 +1,245 @@
+use std::{
+	collections::BTreeMap,
+	io::ErrorKind,
+	path::{Path, PathBuf},
+	time::Instant,
+};
+
+use crate::TempDir;
+
+use super::{
+	catch_panic, collect_files_with_name, collect_test_dirs_with_name, output_result,
+	suppress_panic_output, Format, MissingValid, TestData, TestDataFileResult, TestDataResult,
+	TestDataResultItem, Watchdog,
+};
+
+const TEST_INPUT_DIR_NAME: &str = "input";
+const TEST_EXPECTED_DIR_NAME: &str = "expected";
+const TEST_NEW_DIR_NAME: &str = "expected.new";
+
+impl TestData {
+	/// Runs the tests from case directories, where each case is a directory
+	/// with an `input` and an `expected` subdirectory:
+	///
+	/// ```text
+	/// testdata/
+	///     some_case/
+	///         input/
+	///             config.toml
+	///         expected/
+	///             config.toml
+	///             output/result.txt
+	/// ```
+	///
+	/// For each case, the callback receives a [`TempDir`] with a copy of the
+	/// `input` tree. Once the callback returns, the resulting tree is compared
+	/// file by file with the `expected` tree, and each mismatched file outputs
+	/// its own diff. Missing and unexpected files are also reported. Empty
+	/// directories are not compared.
+	///
+	/// Text files are compared as lines, as in [`TestData::run`]. Files that
+	/// are not valid UTF-8 are compared exactly.
+	///
+	/// Directories without an `input` subdirectory are scanned for nested
+	/// cases. The test name is the case directory path relative to the root.
+	///
+	/// If the `expected` directory does not exist, the case fails and an
+	/// `expected.new` directory is created with the resulting tree. In bless
+	/// mode, or with [`MissingValid::Create`], the `expected` directory is
+	/// replaced with the resulting tree. Text files are redacted as in
+	/// [`TestData::run`], both when comparing and when writing the tree.
+	///
+	/// See also [`testdata_dirs`](super::testdata_dirs).
+	pub fn run_dirs<F>(self, callback: F)
+	where
+		F: FnMut(&TempDir),
+	{
+		let result = self.run_dirs_to_result(callback);
+		output_result(&result);
+	}
+
+	pub(super) fn run_dirs_to_result<F>(&self, mut test_callback: F) -> TestDataResult
+	where
+		F: FnMut(&TempDir),
+	{
+		let mut test_results = Vec::new();
+		let mut skipped = Vec::new();
+		let test_dirs_with_name = collect_test_dirs_with_name(&self.path, TEST_INPUT_DIR_NAME);
+
+		let watchdog = Watchdog::new();
+		let _hook = suppress_panic_output();
+		for (case_path, test_name) in test_dirs_with_name.into_iter() {
+			if !self.matches_filter(&test_name) {
+				skipped.push(test_name);
+				continue;
+			}
+			let start = Instant::now();
+			let mut item = self.run_dir(&case_path, test_name, &watchdog, &mut test_callback);
+			item.duration = start.elapsed();
+			test_results.push(item);
+		}
+
+		self.to_result(test_results, skipped)
+	}
+
+	/// Runs a single case directory and compares the resulting tree with the
+	/// expected tree.
+	fn run_dir<F>(
+		&self,
+		case_path: &Path,
+		test_name: String,
+		watchdog: &Watchdog,
+		test_callback: F,
+	) -> TestDataResultItem
+	where
+		F: FnOnce(&TempDir),
+	{
+		let mut item = TestDataResultItem::new(test_name, TEST_EXPECTED_DIR_NAME.to_string());
+
+		let dir = TempDir::create_new();
+		copy_dir(&case_path.join(TEST_INPUT_DIR_NAME), dir.path());
+		let watch = watchdog.watch(&item.name, self.timeout);
+		let outcome = catch_panic(|| test_callback(&dir));
+		if let Some(timeout) = watch.timed_out() {
+			item.unexpected = Some(format!("timed out after {:?}", timeout));
+			return item;
+		}
+		if let Err(panic) = outcome {
+			item.panic = Some(panic);
+			return item;
+		}
+
+		let expected_path = case_path.join(TEST_EXPECTED_DIR_NAME);
+		let new_path = case_path.join(TEST_NEW_DIR_NAME);
+		if expected_path.is_dir() {
+			item.files = self.compare_dirs(dir.path(), &expected_path);
+			item.success = item.files.is_empty();
+			if !item.success && self.bless {
+				self.write_output_dir(dir.path(), &expected_path);
+				item.blessed = true;
+			}
+		} else if self.bless || self.missing_valid == MissingValid::Create {
+			self.write_output_dir(dir.path(), &expected_path);
+			item.blessed = true;
+		} else {
+			// for convenience, generate the expected tree from the output
+			self.write_output_dir(dir.path(), &new_path);
+		}
+
+		if item.blessed {
+			item.success = true;
+			item.files.clear();
+		}
+
+		if item.success {
+			// any `expected.new` directory is stale once the test passes
+			if let Err(err) = std::fs::remove_dir_all(&new_path) {
+				if err.kind() != ErrorKind::NotFound {
+					panic!("failed to remove stale output for {}: {}", item.name, err);
+				}
+			}
+		}
+
+		item
+	}
+
+	/// Compares the files in the actual and expected directories, returning
+	/// the files that do not match.
+	fn compare_dirs(&self, actual_path: &Path, expected_path: &Path) -> Vec<TestDataFileResult> {
+		let mut files = BTreeMap::<String, (Option<PathBuf>, Option<PathBuf>)>::new();
+		for (path, name) in collect_files_with_name(actual_path) {
+			files.entry(name).or_default().0 = Some(path);
+		}
+		for (path, name) in collect_files_with_name(expected_path) {
+			files.entry(name).or_default().1 = Some(path);
+		}
+
+		let mut mismatched = Vec::new();
+		for (name, (actual_file, expected_file)) in files.into_iter() {
+			let actual = actual_file.map(|x| std::fs::read(x).expect("reading output file"));
+			let actual = actual.map(|x| self.redact_output(Format::Raw, x));
+			let expected = expected_file.map(|x| std::fs::read(x).expect("reading expected file"));
+			let result = match (actual, expected) {
+				(Some(actual), Some(expected)) => {
+					let (actual, expected) = self.file_lines(actual, expected);
+					if actual == expected {
+						continue;
+					}
+					TestDataFileResult {
+						path: name,
+						expect: Some(expected),
+						actual: Some(actual),
+						expect_file: None,
+					}
+				}
+				(actual, expected) => TestDataFileResult {
+					path: name,
+					expect: expected.map(|x| self.file_display(x)),
+					actual: actual.map(|x| self.file_display(x)),
+					expect_file: None,
+				},
+			};
+			mismatched.push(result);
+		}
+		mismatched
+	}
+
+	/// Replaces the target directory with the redacted files from the output
+	/// directory, so that the written files match what is compared.
+	fn write_output_dir(&self, output_path: &Path, target_path: &Path) {
+		if let Err(err) = std::fs::remove_dir_all(target_path) {
+			if err.kind() != ErrorKind::NotFound {
+				panic!("failed to remove `{}`: {}", target_path.display(), err);
+			}
+		}
+		std::fs::create_dir_all(target_path).expect("creating expected directory");
+		for (path, name) in collect_files_with_name(output_path) {
+			let contents = std::fs::read(path).expect("reading output file");
+			let contents = self.redact_output(Format::Raw, contents);
+			let target_file = target_path.join(name);
+			if let Some(parent) = target_file.parent() {
+				std::fs::create_dir_all(parent).expect("creating test directory");
+			}
+			std::fs::write(target_file, contents).expect("writing test output");
+		}
+	}
+
+	/// Returns the lines to compare for the actual and expected file contents.
+	/// Text files are split into lines, other files are compared exactly.
+	fn file_lines(&self, actual: Vec<u8>, expected: Vec<u8>) -> (Vec<String>, Vec<String>) {
+		match (String::from_utf8(actual), String::from_utf8(expected)) {
+			(Ok(actual), Ok(expected)) => (self.read_lines(actual), self.read_lines(expected)),
+			(actual, expected) => {
+				let actual = actual
+					.map(String::into_bytes)
+					.unwrap_or_else(|x| x.into_bytes());
+				let expected = expected
+					.map(String::into_bytes)
+					.unwrap_or_else(|x| x.into_bytes());
+				(
+					Format::Bytes.display_lines(&actual),
+					Format::Bytes.display_lines(&expected),
+				)
+			}
+		}
+	}
+
+	/// Returns the lines to display for a file without a counterpart.
+	fn file_display(&self, contents: Vec<u8>) -> Vec<String> {
+		match String::from_utf8(contents) {
+			Ok(text) => self.read_lines(text),
+			Err(err) => Format::Bytes.display_lines(err.as_bytes()),
+		}
+	}
+}
+
+/// Copies all files from the source directory into the target directory.
+fn copy_dir(source_path: &Path, target_path: &Path) {
+	for (path, name) in collect_files_with_name(source_path) {
+		let target_file = target_path.join(name);
+		if let Some(parent) = target_file.parent() {
+			std::fs::create_dir_all(parent).expect("creating test directory");
+		}
+		std::fs::copy(path, target_file).expect("copying test file");
+	}
+}
@@ -0,0 +1,79 @@
+/// Returns `true` if the test name matches the filter pattern.
+///
+/// A pattern containing `*` or `?` is a glob that must match the whole name,
+/// where `*` matches any sequence of characters (including `/`) and `?`
+/// matches a single character. Any other pattern matches as a substring.
+pub(super) fn matches_filter(name: &str, pattern: &str) -> bool {
+	if pattern.contains(['*', '?']) {
+		let name = name.chars().collect::<Vec<_>>();
+		let pattern = pattern.chars().collect::<Vec<_>>();
+		glob_match(&name, &pattern)
+	} else {
+		name.contains(pattern)
+	}
+}
+
+fn glob_match(name: &[char], pattern: &[char]) -> bool {
+	// position to backtrack to after the last `*`, as pattern and name index
+	let mut backtrack: Option<(usize, usize)> = None;
+	let (mut n, mut p) = (0, 0);
+	while n < name.len() {
+		match pattern.get(p) {
+			Some('*') => {
+				backtrack = Some((p, n));
+				p += 1;
+			}
+			Some(&c) if c == '?' || c == name[n] => {
+				n += 1;
+				p += 1;
+			}
+			_ => {
+				if let Some((star_p, star_n)) = backtrack {
+					// let the last `*` consume one more character
+					backtrack = Some((star_p, star_n + 1));
+					p = star_p + 1;
+					n = star_n + 1;
+				} else {
+					return false;
+				}
+			}
+		}
+	}
+	pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod test_filter {
+	use super::matches_filter;
+
+	#[test]
+	fn matches_substring() {
+		assert!(matches_filter("parser/if_else.input", "if_"));
+		assert!(matches_filter("parser/if_else.input", "parser/"));
+		assert!(!matches_filter("parser/if_else.input", "while"));
+	}
+
+	#[test]
+	fn matches_glob() {
+		assert!(matches_filter("parser/if_else.input", "parser/if*"));
+		assert!(matches_filter("parser/if_else.input", "*else*"));
+		assert!(matches_filter(
+			"parser/if_else.input",
+			"parser/??_else.input"
+		));
+		assert!(matches_filter("a/b/c.input", "*/c.input"));
+		assert!(!matches_filter("parser/if_else.input", "if*"));
+		assert!(!matches_filter(
+			"parser/if_else.input",
+			"parser/?_else.input"
+		));
+		assert!(!matches_filter("parser/while.input", "parser/if*"));
+	}
+
+	#[test]
+	fn glob_backtracks_after_star() {
+		assert!(matches_filter("abcabd", "*abd"));
+		assert!(matches_filter("aaa", "a*a*a"));
+		assert!(!matches_filter("aa", "a*a*a"));
+	}
+}
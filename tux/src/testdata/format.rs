@@ -0,0 +1,129 @@
+use super::{has_placeholders, matches_placeholders};
+
+/// Determines how the test output is compared with the valid file, and how
+/// both are displayed in the failure diff.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) enum Format {
+	/// The valid file is normalized into lines before comparing, and the
+	/// diff shows the lines as is.
+	Lines,
+
+	/// Exact comparison, with visible whitespace in the diff.
+	Raw,
+
+	/// Exact comparison, with a hex dump diff.
+	Bytes,
+}
+
+const HEX_DUMP_BYTES_PER_LINE: usize = 16;
+
+impl Format {
+	/// Returns the lines used to display the contents in the failure diff.
+	pub fn display_lines(&self, contents: &[u8]) -> Vec<String> {
+		match self {
+			Format::Lines => {
+				let text = String::from_utf8_lossy(contents);
+				if text.is_empty() {
+					Vec::new()
+				} else {
+					text.split('\n').map(|x| x.to_string()).collect()
+				}
+			}
+			Format::Raw => visible_whitespace_lines(&String::from_utf8_lossy(contents)),
+			Format::Bytes => hex_dump_lines(contents),
+		}
+	}
+
+	/// Returns `true` if the output matches the placeholders in the expected
+	/// output. Placeholders are only supported for line based comparisons.
+	///
+	/// See [`matches_placeholders`].
+	pub fn matches_placeholders(&self, output: &[u8], expected: &[u8]) -> Result<bool, String> {
+		match self {
+			Format::Lines => {
+				let output = String::from_utf8_lossy(output);
+				let expected = String::from_utf8_lossy(expected);
+				let expected = expected.split('\n').collect::<Vec<_>>();
+				if !has_placeholders(&expected) {
+					return Ok(false);
+				}
+				let output = output.split('\n').collect::<Vec<_>>();
+				matches_placeholders(&output, &expected)
+			}
+			Format::Raw | Format::Bytes => Ok(false),
+		}
+	}
+}
+
+/// Splits the text into lines, making all whitespace visible, including
+/// the line breaks.
+fn visible_whitespace_lines(text: &str) -> Vec<String> {
+	text.split_inclusive('\n')
+		.map(|line| {
+			line.chars()
+				.map(|c| match c {
+					' ' => '·',
+					'\t' => '→',
+					'\r' => '␍',
+					'\n' => '␊',
+					c => c,
+				})
+				.collect()
+		})
+		.collect()
+}
+
+/// Formats the bytes as a hex dump, with the offset, the hex values, and the
+/// printable ASCII characters for each line.
+fn hex_dump_lines(bytes: &[u8]) -> Vec<String> {
+	bytes
+		.chunks(HEX_DUMP_BYTES_PER_LINE)
+		.enumerate()
+		.map(|(index, chunk)| {
+			let hex = chunk
+				.iter()
+				.map(|x| format!("{:02x}", x))
+				.collect::<Vec<_>>();
+			let ascii = chunk
+				.iter()
+				.map(|&x| {
+					if x.is_ascii_graphic() || x == b' ' {
+						x as char
+					} else {
+						'.'
+					}
+				})
+				.collect::<String>();
+			format!(
+				"{:08x}  {:<width$}  |{}|",
+				index * HEX_DUMP_BYTES_PER_LINE,
+				hex.join(" "),
+				ascii,
+				width = HEX_DUMP_BYTES_PER_LINE * 3 - 1
+			)
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod test_format {
+	use super::{hex_dump_lines, visible_whitespace_lines};
+
+	#[test]
+	fn shows_visible_whitespace() {
+		let out = visible_whitespace_lines("a b\t\r\n\nc ");
+		assert_eq!(out, ["a·b→␍␊", "␊", "c·"]);
+	}
+
+	#[test]
+	fn hex_dump() {
+		let out = hex_dump_lines(b"0123456789abcdef\x00\xff!");
+		assert_eq!(
+			out,
+			[
+				"00000000  30 31 32 33 34 35 36 37 38 39 61 62 63 64 65 66  |0123456789abcdef|",
+				"00000010  00 ff 21                                         |..!|",
+			]
+		);
+	}
+}
@@ -0,0 +1,2188 @@
+//! Support for tests based on text files.
+//!
+//! This module is enabled by the `testdata` feature (enabled by default).
+
+use std::path::Path;
+
+#[cfg(feature = "exec")]
+mod bin;
+
+mod builder;
+pub use builder::*;
+
+mod case;
+pub use case::*;
+
+mod catch;
+use catch::*;
+
+mod check;
+
+mod collect;
+use collect::*;
+
+#[cfg(feature = "temp")]
+mod dirs;
+
+mod filter;
+use filter::*;
+
+mod format;
+use format::*;
+
+mod pattern;
+use pattern::*;
+
+mod report;
+pub use report::*;
+
+mod result;
+pub use result::*;
+
+mod sections;
+
+mod stale;
+
+mod timeout;
+use timeout::*;
+
+/// Test all `.input` files in the given directory (recursively) using the
+/// callback and compare the result with the expected output provided by a
+/// `.valid` file alongside the input.
+///
+/// # Test procedure
+///
+/// All `.input` files in the provided directory will be read as text, split
+/// into lines, and passed to the provided callback.
+///
+/// The callback returns a list of output lines that is then compared to the
+/// lines loaded from a `.valid` file with the same name as the input.
+///
+/// The test will fail if the callback output does not match the lines in the
+/// `.valid` file. In this case, the function will output the differences
+/// (see below).
+///
+/// After running the callback for all inputs, if there was any failed test
+/// the function will panic.
+///
+/// ## Input lines
+///
+/// For convenience, both the `.input` and `.valid` files are read into lines
+/// by using the [text::lines()](fn@super::text::lines) function, which
+/// provides some whitespace filtering and normalization.
+///
+/// The use of lines is more convenient for most test cases and the filtering
+/// avoids errors by differences in whitespace.
+///
+/// ## Placeholders
+///
+/// Outputs with values that change on every run, such as timestamps, paths,
+/// or hashes, can use placeholders in the `.valid` file:
+///
+/// - `{{*}}` matches any text within a line;
+/// - `{{re:REGEX}}` matches a regular expression within a line (e.g.
+///   `{{re:[0-9a-f]{40}}}`);
+/// - a line with only `{{...}}` matches any number of lines.
+///
+/// ```text
+/// build started at {{*}}
+/// {{...}}
+/// commit {{re:[0-9a-f]{40}}}
+/// ```
+///
+/// A literal `{{` is written as `\{{`. A line whose only `{{` are escaped
+/// is compared as literal text, with the backslashes removed.
+///
+/// When the output does not match, the diff is shown against the literal
+/// text of the `.valid` file, even if a placeholder is invalid. Placeholders are also supported in `.error`
+/// files and in the output sections of [`testdata_sections`], but not in
+/// the exact comparisons of [`testdata_raw`] and [`testdata_bytes`].
+///
+/// Note that the bless mode replaces a mismatched `.valid` file with the
+/// actual output, so any placeholders need to be restored manually.
+///
+/// ## Failure output
+///
+/// After testing all `.input` files, the function will output a summary of
+/// the tests. For failed tests, [diff::lines](fn@super::diff::lines) will
+/// be used to provide the difference between the actual lines (`source`) and
+/// the expected lines from the `.valid` file (`result`).
+///
+/// The difference is output in the unified diff format (see
+/// [`UnifiedDiff`](super::diff::UnifiedDiff)), showing only the changed
+/// lines with some context around them.
+///
+/// When stderr is a terminal, the diff output uses ANSI colors. This can be
+/// controlled with the `NO_COLOR` environment variable or by setting the
+/// [`ColorMode`](super::diff::ColorMode) with
+/// [`set_color_mode`](super::diff::set_color_mode).
+///
+/// If the callback panics for a test case, the panic message is reported as
+/// the failure for that case and the other test cases still run. The default
+/// panic output is suppressed while the callback runs, as in [`assert_panic!`](crate::assert_panic!).
+///
+/// ## Check directives
+///
+/// Instead of a `.valid` file with the full output, a test case can provide
+/// a `.check` file with [FileCheck](https://llvm.org/docs/CommandGuide/FileCheck.html)
+/// style directives, which are matched against the output lines in order:
+///
+/// - `CHECK: text` matches anywhere after the previous match;
+/// - `CHECK-NEXT: text` matches in the line after the previous match;
+/// - `CHECK-SAME: text` matches in the same line, after the previous match;
+/// - `CHECK-NOT: text` must not match between the previous and the next
+///   match (or the end of the output).
+///
+/// The text matches part of a line, and supports the same `{{*}}` and
+/// `{{re:...}}` placeholders as the `.valid` files. Other lines in the
+/// `.check` file are ignored.
+///
+/// A `.check` file is only used when there is no `.valid` file, and having
+/// both fails the test case.
+///
+/// With [`TestData::input_checks`], the directives can also be given in
+/// comments in the `.input` file itself (e.g. `// CHECK: text`), when there
+/// is neither a `.valid` nor a `.check` file. The input is passed to the
+/// callback as is, including the comments.
+///
+/// When a directive fails, the test output shows the directive, where it
+/// was declared, and the position in the output where it was looked for.
+///
+/// ## Generating valid files
+///
+/// As a convenience feature, if a `.valid` file is not found alongside the
+/// input, the test will fail but will also create a `.valid.new` file with
+/// the actual output.
+///
+/// This feature can be used to easily generate `.valid` files by creating
+/// the `.input` file, running the tests, and then removing the `.new` from
+/// the created file after manually inspecting it to make sure it is the
+/// expected behavior.
+///
+/// Once a test passes, any leftover `.valid.new` file for it is removed.
+///
+/// ## Stale files
+///
+/// After running the tests, the directory is checked for `.valid`, `.error`,
+/// and `.check` files without a matching `.input` (e.g. after renaming an
+/// input), and for `.valid.new` files next to an existing `.valid` file.
+/// These are reported as warnings after the test summary.
+///
+/// With the `TUX_TESTDATA_STRICT` environment variable set to `1`, each of
+/// those files fails the test instead. See [`TestData::strict`].
+///
+/// ## Bless mode
+///
+/// When the expected behavior changes on purpose, the `.valid` files can be
+/// updated by running the tests with the `TUX_BLESS` environment variable
+/// set to `1`:
+///
+/// ```text
+/// TUX_BLESS=1 cargo test
+/// ```
+///
+/// In bless mode, mismatched or missing `.valid` files are overwritten with
+/// the actual test output and the test does not fail. The updated files are
+/// listed in the test output.
+///
+/// ## Filtering test cases
+///
+/// To run only some of the test cases, set the `TUX_TESTDATA_FILTER`
+/// environment variable to a substring or glob pattern matching the test
+/// name, which is the input path relative to the test directory:
+///
+/// ```text
+/// TUX_TESTDATA_FILTER=parser/if* cargo test
+/// ```
+///
+/// The number of skipped test cases is reported in the test output. See
+/// [`TestData::filter`] for details on the pattern.
+///
+/// ## Directives
+///
+/// The `.input` file can start with directive lines in the format
+/// `// tux: directive`, which are removed from the input before it is passed
+/// to the callback:
+///
+/// ```text
+/// // tux: skip=not implemented yet
+/// some input
+/// ```
+///
+/// The `skip` directive does not run the test case, and the `xfail` directive
+/// marks the test case as expected to fail. Both are reported separately in
+/// the test output. See [`TestCase`] for all directives, and [`testdata_with_case`]
+/// to access them from the callback.
+///
+/// ## Timeouts
+///
+/// A test case can set a timeout with the `// tux: timeout=5s` directive, or
+/// use the default timeout set with [`TestData::timeout`]. A test case that
+/// runs past its timeout fails, and a stuck test case is reported by name
+/// while it runs.
+///
+/// The timeout does not interrupt the callback, so a test case that never
+/// returns hangs this function. Hard timeouts require [`TestData::run_isolated`],
+/// which continues with the remaining test cases when a test case never
+/// returns.
+///
+/// ## One test per case
+///
+/// This function runs all test cases as a single test. To have the test
+/// harness run each case as a separate test, use the [`testdata`](macro@crate::testdata)
+/// attribute instead:
+///
+/// ```ignore
+/// #[tux::testdata("tests/testdata/reverse")]
+/// fn reverse(mut lines: Vec<String>) -> Vec<String> {
+///     lines.reverse();
+///     lines
+/// }
+/// ```
+///
+/// ## Redactions
+///
+/// Output with volatile values, such as temporary paths, timestamps, or
+/// durations, can be redacted before comparing with [`TestData::redact`] or
+/// by binding the redactions with [`Redactions::bind`](crate::redact::Redactions::bind).
+/// The `.valid` files then contain placeholders like `[TEMP]` and `[DURATION]`.
+/// Redactions are not applied to [`testdata_bytes`].
+///
+/// This requires the `redact` feature (enabled by default).
+///
+/// ## Reports
+///
+/// If the `TUX_REPORT_DIR` environment variable is set, a JSON and a JUnit
+/// XML report with the result of each test case are written to that
+/// directory. See [`TestDataResult::write_reports`].
+///
+/// ## Configuration
+///
+/// This function uses the default configuration of the [`TestData`] builder,
+/// which can be used to customize the file extensions, the normalization of
+/// input and output lines, and the handling of missing `.valid` files. The
+/// builder can also run the test cases in parallel with [`TestData::run_parallel`].
+pub fn testdata<P, F>(path: P, callback: F)
+where
+	P: AsRef<Path>,
+	F: FnMut(Vec<String>) -> Vec<String>,
+{
+	TestData::new(path).run(callback)
+}
+
+/// Test all `.input` files in the given directory (recursively) using a
+/// callback that also receives the [`TestCase`], and compare the result with
+/// the `.valid` file alongside the input.
+///
+/// This is the same as [`testdata`], but the callback can use the directives
+/// from the header of the input file, such as the test arguments:
+///
+/// ```no_run
+/// # fn parse(input: Vec<String>, strict: bool) -> Vec<String> { input }
+/// tux::testdata_with_case("tests/testdata/parser", |case, input| {
+///     let strict = case.args().iter().any(|x| x == "--strict");
+///     parse(input, strict)
+/// });
+/// ```
+///
+/// Use [`TestData::run_with_case`] for a custom configuration.
+pub fn testdata_with_case<P, F>(path: P, callback: F)
+where
+	P: AsRef<Path>,
+	F: FnMut(&TestCase, Vec<String>) -> Vec<String>,
+{
+	TestData::new(path).run_with_case(callback)
+}
+
+/// Test all `.test` files in the given directory (recursively), where each
+/// file contains one or more test cases with `-- input --` and `-- output --`
+/// sections.
+///
+/// This is the same as [`testdata`], but keeps the input and the expected
+/// output in a single file:
+///
+/// ```text
+/// -- input --
+/// some input
+/// -- output --
+/// expected output
+/// ```
+///
+/// In bless mode, the output sections are rewritten in place. See
+/// [`TestData::run_sections`] for details on the file format.
+pub fn testdata_sections<P, F>(path: P, callback: F)
+where
+	P: AsRef<Path>,
+	F: FnMut(Vec<String>) -> Vec<String>,
+{
+	TestData::new(path).run_sections(callback)
+}
+
+/// Test all case directories in the given directory (recursively), where
+/// each case has an `input` and an `expected` subdirectory.
+///
+/// The callback receives a [`TempDir`](crate::TempDir) populated with a copy
+/// of the `input` tree, and can modify it as needed. The resulting tree is
+/// then compared file by file with the `expected` tree, with a diff for each
+/// mismatched file:
+///
+/// ```no_run
+/// # use tux::testdata_dirs;
+/// # fn format_files(path: &std::path::Path) {}
+/// testdata_dirs("tests/testdata/format", |dir| {
+///     format_files(dir.path());
+/// });
+/// ```
+///
+/// See [`TestData::run_dirs`] for details.
+///
+/// This requires the `temp` feature (enabled by default).
+#[cfg(feature = "temp")]
+pub fn testdata_dirs<P, F>(path: P, callback: F)
+where
+	P: AsRef<Path>,
+	F: FnMut(&crate::TempDir),
+{
+	TestData::new(path).run_dirs(callback)
+}
+
+/// Test all `.input` files in the given directory (recursively) by running
+/// a binary from the project with the input as standard input, and compare
+/// the output with the `.stdout` file alongside the input.
+///
+/// The error output and the exit code are also compared with the optional
+/// `.stderr` and `.status` files. The arguments and environment variables
+/// for each case can be set with `.args` and `.env` files:
+///
+/// ```text
+/// testdata/
+///     hello.input
+///     hello.args      # --name world
+///     hello.stdout    # hello world
+/// ```
+///
+/// ```no_run
+/// tux::testdata_bin("mybin", "tests/testdata/mybin");
+/// ```
+///
+/// See [`TestData::run_bin`] for details.
+///
+/// This requires the `exec` feature (enabled by default).
+#[cfg(feature = "exec")]
+pub fn testdata_bin<P: AsRef<Path>>(name: &str, path: P) {
+	TestData::new(path).run_bin(name)
+}
+
+/// Test all `.input` files in the given directory (recursively) using a
+/// fallible callback. The output is compared with the `.valid` file, and an
+/// error with the `.error` file alongside the input.
+///
+/// This is the same as [`testdata`], but allows testing inputs that should
+/// fail. The error is formatted with [`Display`](std::fmt::Display) and
+/// normalized into lines in the same way as the output.
+///
+/// ```no_run
+/// # fn parse(input: Vec<String>) -> Result<Vec<String>, String> { Ok(input) }
+/// tux::testdata_fallible("tests/testdata/parser", |input| parse(input));
+/// ```
+///
+/// A test case fails if the callback returns an error when only the `.valid`
+/// file exists, or succeeds when only the `.error` file exists. If neither
+/// file exists, the test fails and a `.valid.new` or `.error.new` file is
+/// generated, as with [`testdata`]. The bless mode also applies to `.error`
+/// files.
+///
+/// Use [`TestData::run_fallible`] for a custom configuration.
+pub fn testdata_fallible<P, F, E>(path: P, callback: F)
+where
+	P: AsRef<Path>,
+	F: FnMut(Vec<String>) -> Result<Vec<String>, E>,
+	E: std::fmt::Display,
+{
+	TestData::new(path).run_fallible(callback)
+}
+
+/// Test all `.input` files in the given directory (recursively) using the
+/// callback with the raw input text, and compare the result exactly with the
+/// `.valid` file alongside the input.
+///
+/// This is the same as [`testdata`], except that the files are not split
+/// into lines and no whitespace is trimmed. This is useful for testing
+/// formatters and code generators, where whitespace is significant.
+///
+/// When a test fails, whitespace in the diff is made visible: spaces are
+/// shown as `·`, tabs as `→`, carriage returns as `␍`, and line breaks as
+/// `␊`.
+///
+/// Use [`TestData::run_raw`] for a custom configuration.
+pub fn testdata_raw<P, F>(path: P, callback: F)
+where
+	P: AsRef<Path>,
+	F: FnMut(String) -> String,
+{
+	TestData::new(path).run_raw(callback)
+}
+
+/// Test all `.input` files in the given directory (recursively) using the
+/// callback with the input file bytes, and compare the result exactly with
+/// the `.valid` file alongside the input.
+///
+/// This is the same as [`testdata`] for binary data. When a test fails, the
+/// diff is output as a hex dump of the output and the valid file.
+///
+/// Use [`TestData::run_bytes`] for a custom configuration.
+pub fn testdata_bytes<P, F>(path: P, callback: F)
+where
+	P: AsRef<Path>,
+	F: FnMut(Vec<u8>) -> Vec<u8>,
+{
+	TestData::new(path).run_bytes(callback)
+}
+
+/// Implementation of the tests generated by the [`testdata`](macro@crate::testdata)
+/// attribute. Runs a single test case from the directory.
+#[doc(hidden)]
+pub fn testdata_case<F>(path: &str, name: &str, callback: F)
+where
+	F: FnMut(Vec<String>) -> Vec<String>,
+{
+	TestData::new(path).case(name).run(callback)
+}
+
+#[cfg(test)]
+#[cfg(feature = "temp")] // we use `temp` in the tests
+mod test_testdata {
+	use std::time::Duration;
+
+	use super::{MissingValid, TestData, TestDataResult};
+	#[cfg(feature = "redact")]
+	use crate::redact::Redactions;
+	use crate::{assert_panic, temp_dir, TempDir};
+
+	fn testdata_to_result<F>(test_path: &std::path::Path, test_callback: F) -> TestDataResult
+	where
+		F: FnMut(Vec<String>) -> Vec<String>,
+	{
+		TestData::without_env(test_path).run_to_result(test_callback)
+	}
+
+	#[test]
+	fn runs_test_callback() {
+		let dir = temp_dir();
+		dir.create_file("some.input", "");
+		dir.create_file("some.valid", "");
+
+		let mut test_callback_was_called = false;
+		TestData::without_env(dir.path()).run(|input| {
+			test_callback_was_called = true;
+			input
+		});
+
+		assert!(test_callback_was_called);
+	}
+
+	#[test]
+	fn runs_test_callback_with_input() {
+		let dir = temp_dir();
+		dir.create_file("some.input", "the input");
+		dir.create_file("some.valid", "");
+
+		let mut test_callback_input = String::new();
+		TestData::without_env(dir.path()).run(|input| {
+			let input = input.join("\n");
+			test_callback_input.push_str(&input);
+			Vec::new()
+		});
+
+		assert_eq!(test_callback_input, "the input");
+	}
+
+	#[test]
+	fn fails_if_output_is_missing() {
+		let dir = temp_dir();
+		dir.create_file("test.input", "some input");
+
+		let res = testdata_to_result(dir.path(), |input| input);
+		assert!(!res.success());
+	}
+
+	#[test]
+	fn fails_if_output_is_different() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "some input", "some output");
+
+		let res = testdata_to_result(dir.path(), |input| input);
+		assert!(!res.success());
+	}
+
+	#[test]
+	fn runs_test_callback_for_each_input() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "input A", "");
+		helper::write_case(&dir, "b.input", "input B", "");
+		helper::write_case(&dir, "c.input", "input C", "");
+
+		let mut test_callback_inputs = Vec::new();
+		TestData::without_env(dir.path()).run(|input| {
+			let input = input.join("\n");
+			test_callback_inputs.push(input);
+			Vec::new()
+		});
+
+		let expected = vec![
+			"input A".to_string(),
+			"input B".to_string(),
+			"input C".to_string(),
+		];
+		assert_eq!(test_callback_inputs, expected);
+	}
+
+	#[test]
+	fn recurses_into_subdirectories() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a1.input", "a1", "");
+		helper::write_case(&dir, "a2.input", "a2", "");
+		helper::write_case(&dir, "a3.input", "a3", "");
+		helper::write_case(&dir, "a1/a.input", "a1/a", "");
+		helper::write_case(&dir, "a1/b.input", "a1/b", "");
+		helper::write_case(&dir, "a2/a.input", "a2/a", "");
+		helper::write_case(&dir, "a2/b.input", "a2/b", "");
+		helper::write_case(&dir, "a2/sub/file.input", "a2/sub/file", "");
+
+		let mut test_callback_inputs = Vec::new();
+		TestData::without_env(dir.path()).run(|input| {
+			let input = input.join("\n");
+			test_callback_inputs.push(input);
+			Vec::new()
+		});
+
+		let expected = vec![
+			"a1".to_string(),
+			"a2".to_string(),
+			"a3".to_string(),
+			"a1/a".to_string(),
+			"a1/b".to_string(),
+			"a2/a".to_string(),
+			"a2/b".to_string(),
+			"a2/sub/file".to_string(),
+		];
+		assert_eq!(test_callback_inputs, expected);
+	}
+
+	#[test]
+	fn fails_and_generate_an_output_file_if_one_does_not_exist() {
+		let dir = temp_dir();
+		dir.create_file("test.input", "Some Input");
+
+		let result = testdata_to_result(dir.path(), |input| {
+			input.into_iter().map(|x| x.to_lowercase()).collect()
+		});
+		assert!(!result.success());
+
+		let new_result_path = dir.path().join("test.valid.new");
+		assert!(new_result_path.is_file());
+
+		let new_result_text = std::fs::read_to_string(new_result_path).unwrap();
+		assert_eq!(new_result_text, "some input");
+	}
+
+	#[test]
+	fn trims_input_files() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "\n\nfirst\ntrim end:  \nlast\n\n", "");
+
+		let mut test_input = Vec::new();
+		TestData::without_env(dir.path()).run(|input| {
+			test_input = input;
+			Vec::new()
+		});
+
+		assert_eq!(test_input, vec!["first", "trim end:", "last"]);
+	}
+
+	#[test]
+	fn trims_expected_output_files() {
+		let dir = temp_dir();
+		helper::write_case(
+			&dir,
+			"test.input",
+			"line 1\nline 2\nline 3",
+			"\n\nline 1\nline 2  \nline 3\n\n",
+		);
+		TestData::without_env(dir.path()).run(|input| input);
+	}
+
+	#[test]
+	fn ignores_line_break_differences_in_input_and_output() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a\nb\nc", "c\r\nb\r\na");
+		helper::write_case(&dir, "b.input", "a\r\nb\r\nc", "c\nb\na");
+
+		TestData::without_env(dir.path()).run(|mut input| {
+			input.reverse();
+			input
+		});
+	}
+
+	#[test]
+	fn does_not_ignore_trailing_indentation_of_first_line() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "value", "  value");
+		let res = testdata_to_result(dir.path(), |input| input);
+		assert!(!res.success());
+	}
+
+	#[test]
+	fn removes_stale_new_valid_file_once_test_passes() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "some input", "some input");
+		dir.create_file("test.valid.new", "old output");
+
+		let result = testdata_to_result(dir.path(), |input| input);
+		assert!(result.success());
+		assert!(!dir.path().join("test.valid.new").exists());
+	}
+
+	//------------------------------------------------------------------------//
+	// Bless mode
+	//------------------------------------------------------------------------//
+
+	#[test]
+	fn bless_overwrites_mismatched_valid_file() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "A", "a");
+		helper::write_case(&dir, "b.input", "B", "old output");
+
+		let result = TestData::without_env(dir.path())
+			.bless(true)
+			.run_to_result(|input| input.into_iter().map(|x| x.to_lowercase()).collect());
+
+		assert!(result.success());
+		assert!(!result.tests[0].blessed);
+		assert!(result.tests[1].blessed);
+
+		let valid_text = std::fs::read_to_string(dir.path().join("b.valid")).unwrap();
+		assert_eq!(valid_text, "b");
+	}
+
+	#[test]
+	fn bless_creates_missing_valid_file() {
+		let dir = temp_dir();
+		dir.create_file("test.input", "Some Input");
+		dir.create_file("test.valid.new", "old output");
+
+		let result = TestData::without_env(dir.path())
+			.bless(true)
+			.run_to_result(|input| input);
+		assert!(result.success());
+		assert!(result.tests[0].blessed);
+
+		let valid_text = std::fs::read_to_string(dir.path().join("test.valid")).unwrap();
+		assert_eq!(valid_text, "Some Input");
+		assert!(!dir.path().join("test.valid.new").exists());
+	}
+
+	//------------------------------------------------------------------------//
+	// TestData builder
+	//------------------------------------------------------------------------//
+
+	#[test]
+	fn builder_supports_custom_extensions() {
+		let dir = temp_dir();
+		dir.create_file("a.in.txt", "A");
+		dir.create_file("a.out.txt", "a");
+		dir.create_file("b.input", "ignored");
+
+		let result = TestData::without_env(dir.path())
+			.input_extension("in.txt")
+			.valid_extension("out.txt")
+			.run_to_result(|input| input.into_iter().map(|x| x.to_lowercase()).collect());
+
+		assert!(result.success());
+		assert_eq!(result.tests.len(), 1);
+		assert_eq!(result.tests[0].name, "a.in.txt");
+		assert_eq!(result.tests[0].valid_file, "a.out.txt");
+	}
+
+	#[test]
+	fn builder_generates_new_file_with_custom_extension() {
+		let dir = temp_dir();
+		dir.create_file("test.in", "some input");
+
+		let result = TestData::without_env(dir.path())
+			.input_extension("in")
+			.valid_extension("out")
+			.run_to_result(|input| input);
+		assert!(!result.success());
+
+		let new_result_text = std::fs::read_to_string(dir.path().join("test.out.new")).unwrap();
+		assert_eq!(new_result_text, "some input");
+	}
+
+	#[test]
+	fn builder_can_disable_trimming() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "\n  a  \nb", "\n  a  \nb\n");
+
+		let mut test_input = Vec::new();
+		let result = TestData::without_env(dir.path())
+			.trim(false)
+			.run_to_result(|input| {
+				test_input = input.clone();
+				input
+			});
+
+		assert!(result.success());
+		assert_eq!(test_input, vec!["", "  a  ", "b"]);
+
+		let result = TestData::without_env(dir.path())
+			.trim(false)
+			.run_to_result(|input| input.into_iter().map(|x| x.trim().to_string()).collect());
+		assert!(!result.success());
+	}
+
+	#[test]
+	fn builder_can_create_missing_valid_files() {
+		let dir = temp_dir();
+		dir.create_file("test.input", "some input");
+
+		let result = TestData::without_env(dir.path())
+			.missing_valid(MissingValid::Create)
+			.run_to_result(|input| input);
+		assert!(result.success());
+
+		let valid_text = std::fs::read_to_string(dir.path().join("test.valid")).unwrap();
+		assert_eq!(valid_text, "some input");
+		assert!(!dir.path().join("test.valid.new").exists());
+	}
+
+	#[test]
+	fn builder_run_panics_on_failure() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "some input", "some output");
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in TestData::without_env(&path).run(|input| input));
+	}
+
+	#[test]
+	fn builder_filters_cases_by_substring() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "parser/if.input", "a", "a");
+		helper::write_case(&dir, "parser/while.input", "b", "b");
+		helper::write_case(&dir, "lexer/if.input", "c", "c");
+
+		let mut inputs = Vec::new();
+		let result = TestData::without_env(dir.path())
+			.filter("if")
+			.run_to_result(|input| {
+				inputs.push(input.join(""));
+				input
+			});
+
+		assert_eq!(inputs, ["c", "a"]);
+		assert_eq!(result.tests.len(), 2);
+		assert_eq!(result.skipped, ["parser/while.input"]);
+		assert_eq!(result.filter.as_deref(), Some("if"));
+	}
+
+	#[test]
+	fn builder_filters_cases_by_glob() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "parser/if.input", "a", "a");
+		helper::write_case(&dir, "parser/if_else.input", "b", "b");
+		helper::write_case(&dir, "lexer/if.input", "c", "c");
+
+		let result = TestData::without_env(dir.path())
+			.filter("parser/if*")
+			.run_to_result(|input| input);
+
+		let names = result
+			.tests
+			.iter()
+			.map(|x| x.name.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(names, ["parser/if.input", "parser/if_else.input"]);
+		assert_eq!(result.skipped, ["lexer/if.input"]);
+	}
+
+	#[test]
+	fn builder_filter_does_not_hide_failures() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "pass.input", "a", "a");
+		helper::write_case(&dir, "fail.input", "a", "b");
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in TestData::without_env(&path).filter("fail").run(|input| input));
+		TestData::without_env(dir.path())
+			.filter("pass")
+			.run(|input| input);
+	}
+
+	#[test]
+	fn tests_are_not_affected_by_the_environment() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "b");
+
+		// this is the only test that changes these variables, since all other
+		// tests use a configuration that ignores them
+		std::env::set_var(super::TESTDATA_BLESS_VAR, "1");
+		std::env::set_var(super::TESTDATA_STRICT_VAR, "1");
+		std::env::set_var(super::TESTDATA_FILTER_VAR, "other");
+		let from_env = TestData::new(dir.path()).run_to_result(|input| input);
+		let result = TestData::without_env(dir.path()).run_to_result(|input| input);
+		std::env::remove_var(super::TESTDATA_BLESS_VAR);
+		std::env::remove_var(super::TESTDATA_STRICT_VAR);
+		std::env::remove_var(super::TESTDATA_FILTER_VAR);
+
+		assert_eq!(from_env.skipped, ["a.input"]);
+		assert!(result.skipped.is_empty());
+		assert!(!result.success());
+		assert!(!result.tests[0].blessed);
+		let valid_text = std::fs::read_to_string(dir.path().join("a.valid")).unwrap();
+		assert_eq!(valid_text, "b");
+	}
+
+	#[test]
+	fn builder_runs_single_case() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "sub/a.input", "b", "fail");
+
+		let result = TestData::without_env(dir.path())
+			.filter("sub")
+			.case("a.input")
+			.run_to_result(|input| input);
+		assert_eq!(result.tests.len(), 1);
+		assert!(result.success());
+		assert!(result.skipped.is_empty());
+
+		let path = dir.path().to_owned();
+		assert_panic!("test case `b.input` not found" in TestData::without_env(&path).case("b.input").run(|input| input));
+	}
+
+	#[test]
+	fn parallel_runs_all_cases_in_sorted_order() {
+		let dir = temp_dir();
+		for i in 0..50 {
+			let expected = if i % 7 == 0 { "fail" } else { "ok" };
+			helper::write_case(&dir, &format!("case{:02}.input", i), "ok", expected);
+		}
+
+		let calls = std::sync::atomic::AtomicUsize::new(0);
+		let result = TestData::without_env(dir.path()).run_parallel_to_result(|input| {
+			calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+			input
+		});
+
+		assert_eq!(calls.into_inner(), 50);
+		let names = result
+			.tests
+			.iter()
+			.map(|x| x.name.clone())
+			.collect::<Vec<_>>();
+		let expected_names = (0..50)
+			.map(|i| format!("case{:02}.input", i))
+			.collect::<Vec<_>>();
+		assert_eq!(names, expected_names);
+		for (i, it) in result.tests.iter().enumerate() {
+			assert_eq!(it.success, i % 7 != 0, "success for {}", it.name);
+		}
+	}
+
+	#[test]
+	fn parallel_applies_filter() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "b.input", "b", "b");
+		let result = TestData::without_env(dir.path())
+			.filter("b")
+			.run_parallel_to_result(|input| input);
+		assert_eq!(result.tests.len(), 1);
+		assert_eq!(result.skipped, ["a.input"]);
+	}
+
+	#[test]
+	fn parallel_run_panics_on_failure() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "b.input", "b", "fail");
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in TestData::without_env(&path).run_parallel(|input| input));
+		TestData::without_env(dir.path())
+			.filter("a")
+			.run_parallel(|input| input);
+	}
+
+	#[test]
+	fn records_panic_in_callback_and_runs_other_cases() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "b.input", "panic", "b");
+		helper::write_case(&dir, "c.input", "c", "c");
+
+		let result = TestData::without_env(dir.path()).run_to_result(|input| {
+			if input[0] == "panic" {
+				panic!("callback panicked with {}", input[0]);
+			}
+			input
+		});
+
+		assert_eq!(result.tests.len(), 3);
+		assert!(result.tests[0].success);
+		assert!(result.tests[2].success);
+
+		let failed = &result.tests[1];
+		assert!(!failed.success);
+		let panic = failed.panic.as_ref().unwrap();
+		assert!(
+			panic.starts_with("callback panicked with panic (at "),
+			"{}",
+			panic
+		);
+		assert!(panic.contains("mod.rs"), "{}", panic);
+		assert!(!dir.path().join("b.valid.new").exists());
+	}
+
+	#[test]
+	fn counts_panicked_cases_as_failed() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "fail");
+		helper::write_case(&dir, "b.input", "b", "b");
+		let path = dir.path().to_owned();
+		assert_panic!("2 test cases failed" in TestData::without_env(&path).run(|_| panic!("some panic")));
+	}
+
+	#[test]
+	fn parallel_records_panic_in_callback() {
+		let dir = temp_dir();
+		for i in 0..10 {
+			helper::write_case(
+				&dir,
+				&format!("case{}.input", i),
+				&i.to_string(),
+				&i.to_string(),
+			);
+		}
+
+		let result = TestData::without_env(dir.path()).run_parallel_to_result(|input| {
+			if input[0] == "3" || input[0] == "7" {
+				panic!("panic at {}", input[0]);
+			}
+			input
+		});
+
+		for (i, it) in result.tests.iter().enumerate() {
+			if i == 3 || i == 7 {
+				let panic = it.panic.as_ref().unwrap();
+				assert!(panic.starts_with(&format!("panic at {}", i)), "{}", panic);
+			} else {
+				assert!(it.success);
+				assert!(it.panic.is_none());
+			}
+		}
+	}
+
+	//------------------------------------------------------------------------//
+	// Directives
+	//------------------------------------------------------------------------//
+
+	#[test]
+	fn directives_are_removed_from_input() {
+		let dir = temp_dir();
+		helper::write_case(
+			&dir,
+			"test.input",
+			"// tux: args=-x\nline 1\nline 2",
+			"line 1\nline 2",
+		);
+
+		let result = TestData::without_env(dir.path()).run_to_result(|input| input);
+		assert!(result.success());
+	}
+
+	#[test]
+	fn callback_receives_test_case() {
+		let dir = temp_dir();
+		helper::write_case(
+			&dir,
+			"sub/test.input",
+			"// tux: args=-a -b\ninput",
+			"input -a -b",
+		);
+
+		let result = TestData::without_env(dir.path()).run_with_case_to_result(|case, input| {
+			assert_eq!(case.name(), "sub/test.input");
+			vec![format!("{} {}", input.join(""), case.args().join(" "))]
+		});
+		assert!(result.success());
+	}
+
+	#[test]
+	fn skip_directive_does_not_run_case() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "// tux: skip=not supported\na", "fail");
+		helper::write_case(&dir, "b.input", "// tux: skip\nb", "fail");
+		helper::write_case(&dir, "c.input", "c", "c");
+
+		let mut inputs = Vec::new();
+		let result = TestData::without_env(dir.path()).run_to_result(|input| {
+			inputs.push(input.join(""));
+			input
+		});
+		assert_eq!(inputs, ["c"]);
+		assert!(result.success());
+		assert_eq!(result.tests[0].skipped.as_deref(), Some("not supported"));
+		assert_eq!(result.tests[1].skipped.as_deref(), Some(""));
+		assert_eq!(result.tests[2].skipped, None);
+
+		// skipped cases don't fail the run
+		TestData::without_env(dir.path()).run(|input| input);
+	}
+
+	#[test]
+	fn xfail_directive_expects_failure() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "mismatch.input", "// tux: xfail\na", "b");
+		helper::write_case(&dir, "panic.input", "// tux: xfail\npanic", "panic");
+		dir.create_file("missing.input", "// tux: xfail\na");
+
+		let result = TestData::without_env(dir.path()).run_to_result(|input| {
+			if input[0] == "panic" {
+				panic!("some panic");
+			}
+			input
+		});
+		assert!(result.success());
+		assert!(result.tests.iter().all(|x| x.expected_failure));
+		assert!(!dir.path().join("missing.valid.new").exists());
+	}
+
+	#[test]
+	fn xfail_directive_fails_if_case_passes() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "// tux: xfail\na", "a");
+
+		let result = TestData::without_env(dir.path()).run_to_result(|input| input);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		assert!(!item.expected_failure);
+		assert_eq!(
+			item.unexpected.as_deref(),
+			Some("passed, but is marked as expected to fail")
+		);
+	}
+
+	#[test]
+	fn xfail_directive_is_not_blessed() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "// tux: xfail\na", "b");
+
+		let result = TestData::without_env(dir.path())
+			.bless(true)
+			.run_to_result(|input| input);
+		assert!(result.tests[0].expected_failure);
+		assert!(!result.tests[0].blessed);
+
+		let valid = std::fs::read_to_string(dir.path().join("test.valid")).unwrap();
+		assert_eq!(valid, "b");
+	}
+
+	#[test]
+	fn invalid_directive_fails_case() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "// tux: skp\na", "a");
+
+		let result = TestData::without_env(dir.path()).run_to_result(|input| input);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		assert_eq!(item.unexpected.as_deref(), Some("invalid directive `skp`"));
+	}
+
+	#[test]
+	fn raw_input_does_not_parse_directives() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "// tux: skip\n", "// tux: skip\n");
+		let result = TestData::without_env(dir.path()).run_raw_to_result(|input| input);
+		assert!(result.success());
+		assert_eq!(result.tests[0].skipped, None);
+	}
+
+	//------------------------------------------------------------------------//
+	// Sections
+	//------------------------------------------------------------------------//
+
+	fn reverse(mut input: Vec<String>) -> Vec<String> {
+		input.reverse();
+		input
+	}
+
+	#[test]
+	fn sections_run_each_case_in_file() {
+		let dir = temp_dir();
+		dir.create_file(
+			"sub/a.test",
+			"-- input --\n1\n2\n-- output --\n2\n1\n-- input --\na\n-- output --\nfail\n",
+		);
+		dir.create_file("b.test", "-- input --\nx\ny\n-- output --\ny\nx");
+
+		let result = TestData::without_env(dir.path()).run_sections_to_result(reverse);
+		let names = result
+			.tests
+			.iter()
+			.map(|x| x.name.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(names, ["b.test", "sub/a.test#1", "sub/a.test#2"]);
+		assert!(result.tests[0].success);
+		assert!(result.tests[1].success);
+		assert!(!result.tests[2].success);
+		assert_eq!(result.tests[2].valid_file, "a.test");
+		assert_eq!(result.tests[2].actual, ["a"]);
+		assert_eq!(result.tests[2].expect, Some(vec!["fail".to_string()]));
+	}
+
+	#[test]
+	fn sections_fail_without_output() {
+		let dir = temp_dir();
+		dir.create_file("a.test", "-- input --\na\n");
+
+		let result = TestData::without_env(dir.path()).run_sections_to_result(reverse);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		assert_eq!(
+			item.unexpected.as_deref(),
+			Some("has no `-- output --` section")
+		);
+		assert_eq!(item.actual, ["a"]);
+
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in TestData::without_env(&path).run_sections(reverse));
+	}
+
+	#[test]
+	fn sections_bless_rewrites_output_in_place() {
+		let dir = temp_dir();
+		let text = "comment\n-- input --\na\nb\n-- output --\nold\n-- input --\nc\n-- output --\nc\n-- input --\nd\ne";
+		dir.create_file("a.test", text);
+
+		let result = TestData::without_env(dir.path())
+			.bless(true)
+			.run_sections_to_result(reverse);
+		assert!(result.success());
+		let blessed = result.tests.iter().map(|x| x.blessed).collect::<Vec<_>>();
+		assert_eq!(blessed, [true, false, true]);
+
+		let text = std::fs::read_to_string(dir.path().join("a.test")).unwrap();
+		assert_eq!(
+			text,
+			"comment\n-- input --\na\nb\n-- output --\nb\na\n-- input --\nc\n-- output --\nc\n-- input --\nd\ne\n-- output --\ne\nd\n"
+		);
+	}
+
+	#[test]
+	fn sections_bless_replaces_invalid_placeholder() {
+		let dir = temp_dir();
+		dir.create_file("a.test", "-- input --\na\n-- output --\n{{re:(}}");
+
+		let result = TestData::without_env(dir.path()).run_sections_to_result(reverse);
+		assert!(!result.success());
+		assert!(result.tests[0].unexpected.is_some());
+
+		let result = TestData::without_env(dir.path())
+			.bless(true)
+			.run_sections_to_result(reverse);
+		let item = &result.tests[0];
+		assert!(item.success && item.blessed);
+		assert_eq!(item.unexpected, None);
+		let text = std::fs::read_to_string(dir.path().join("a.test")).unwrap();
+		assert_eq!(text, "-- input --\na\n-- output --\na\n");
+	}
+
+	#[test]
+	fn sections_support_directives_and_filter() {
+		let dir = temp_dir();
+		dir.create_file(
+			"a.test",
+			"-- input --\n// tux: skip\na\n-- input --\n// tux: xfail\nb\n-- output --\nfail\n-- input --\nc\n-- output --\nc\n",
+		);
+
+		let result = TestData::without_env(dir.path()).run_sections_to_result(reverse);
+		assert!(result.success());
+		assert!(result.tests[0].skipped.is_some());
+		assert!(result.tests[1].expected_failure);
+
+		let result = TestData::without_env(dir.path())
+			.filter("#3")
+			.run_sections_to_result(reverse);
+		assert_eq!(result.tests.len(), 1);
+		assert_eq!(result.skipped, ["a.test#1", "a.test#2"]);
+	}
+
+	#[test]
+	fn sections_report_invalid_files() {
+		let dir = temp_dir();
+		dir.create_file("a.test", "no sections");
+
+		let result = TestData::without_env(dir.path()).run_sections_to_result(reverse);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		assert_eq!(item.name, "a.test");
+		assert_eq!(
+			item.unexpected.as_deref(),
+			Some("no `-- input --` section found")
+		);
+	}
+
+	//------------------------------------------------------------------------//
+	// Fallible
+	//------------------------------------------------------------------------//
+
+	fn parse_number(input: Vec<String>) -> Result<Vec<String>, String> {
+		let number = input.join("");
+		match number.parse::<i32>() {
+			Ok(value) => Ok(vec![format!("{}", value * 2)]),
+			Err(err) => Err(format!("invalid number `{}`\n{}\n", number, err)),
+		}
+	}
+
+	#[test]
+	fn fallible_compares_output_and_error() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "ok.input", "21", "42");
+		dir.create_file("err.input", "abc");
+		dir.create_file(
+			"err.error",
+			"invalid number `abc`\ninvalid digit found in string",
+		);
+
+		let result = TestData::without_env(dir.path()).run_fallible_to_result(parse_number);
+		assert!(result.success());
+		assert_eq!(result.tests[0].valid_file, "err.error");
+		assert_eq!(result.tests[1].valid_file, "ok.valid");
+	}
+
+	#[test]
+	fn fallible_fails_if_error_does_not_match() {
+		let dir = temp_dir();
+		dir.create_file("err.input", "abc");
+		dir.create_file("err.error", "some other error");
+
+		let result = TestData::without_env(dir.path()).run_fallible_to_result(parse_number);
+		assert!(!result.success());
+
+		let item = &result.tests[0];
+		assert_eq!(
+			item.actual,
+			["invalid number `abc`", "invalid digit found in string"]
+		);
+		assert_eq!(item.expect, Some(vec!["some other error".to_string()]));
+	}
+
+	#[test]
+	fn fallible_fails_if_error_is_unexpected() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "err.input", "abc", "123");
+
+		let result = TestData::without_env(dir.path()).run_fallible_to_result(parse_number);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		assert_eq!(
+			item.unexpected.as_deref(),
+			Some("failed with an error, but `err.valid` expects an output")
+		);
+		assert!(!dir.path().join("err.error.new").exists());
+	}
+
+	#[test]
+	fn fallible_fails_if_error_is_expected() {
+		let dir = temp_dir();
+		dir.create_file("ok.input", "1");
+		dir.create_file("ok.error", "some error");
+
+		let result = TestData::without_env(dir.path()).run_fallible_to_result(parse_number);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		assert_eq!(
+			item.unexpected.as_deref(),
+			Some("succeeded, but `ok.error` expects an error")
+		);
+		assert!(!dir.path().join("ok.valid.new").exists());
+
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in TestData::without_env(&path).run_fallible(parse_number));
+	}
+
+	#[test]
+	fn fallible_generates_new_error_file() {
+		let dir = temp_dir();
+		dir.create_file("err.input", "x");
+
+		let result = TestData::without_env(dir.path()).run_fallible_to_result(parse_number);
+		assert!(!result.success());
+		assert_eq!(result.tests[0].valid_file, "err.error");
+
+		let new_file = dir.path().join("err.error.new");
+		let text = std::fs::read_to_string(new_file).unwrap();
+		assert_eq!(text, "invalid number `x`\ninvalid digit found in string");
+	}
+
+	#[test]
+	fn fallible_bless_replaces_valid_with_error() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "err.input", "abc", "123");
+		dir.create_file("err.valid.new", "stale");
+
+		let result = TestData::without_env(dir.path())
+			.bless(true)
+			.run_fallible_to_result(parse_number);
+		assert!(result.success());
+		assert!(result.tests[0].blessed);
+
+		assert!(!dir.path().join("err.valid").exists());
+		assert!(!dir.path().join("err.valid.new").exists());
+		let text = std::fs::read_to_string(dir.path().join("err.error")).unwrap();
+		assert_eq!(text, "invalid number `abc`\ninvalid digit found in string");
+	}
+
+	//------------------------------------------------------------------------//
+	// Raw and bytes
+	//------------------------------------------------------------------------//
+
+	#[test]
+	fn raw_runs_callback_with_exact_input() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "\n  a  \r\n\n", "\n  a  \r\n\n");
+
+		let mut inputs = Vec::new();
+		let result = TestData::without_env(dir.path()).run_raw_to_result(|input| {
+			inputs.push(input.clone());
+			input
+		});
+		assert_eq!(inputs, ["\n  a  \r\n\n"]);
+		assert!(result.success());
+	}
+
+	#[test]
+	fn raw_compares_whitespace_exactly() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "a \n", "a\n");
+
+		let result = TestData::without_env(dir.path()).run_raw_to_result(|input| input);
+		assert!(!result.success());
+
+		let item = &result.tests[0];
+		assert_eq!(item.actual, ["a·␊"]);
+		assert_eq!(item.expect, Some(vec!["a␊".to_string()]));
+	}
+
+	#[test]
+	fn raw_generates_new_file_with_exact_output() {
+		let dir = temp_dir();
+		dir.create_file("test.input", "");
+
+		let result = TestData::without_env(dir.path()).run_raw_to_result(|_| "out  \n\n".into());
+		assert!(!result.success());
+
+		let new_file = dir.path().join("test.valid.new");
+		assert_eq!(std::fs::read_to_string(new_file).unwrap(), "out  \n\n");
+	}
+
+	#[test]
+	fn raw_fails_with_visible_whitespace() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "a\tb", "a b");
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in TestData::without_env(&path).run_raw(|input| input));
+		let result = TestData::without_env(dir.path()).run_raw_to_result(|input| input);
+		assert_eq!(result.tests[0].actual, ["a→b"]);
+	}
+
+	#[test]
+	fn bytes_compares_exact_bytes() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "");
+		std::fs::write(dir.path().join("a.valid"), [0u8, 1, 2, 255]).unwrap();
+		dir.create_file("b.input", "");
+		std::fs::write(dir.path().join("b.valid"), [0u8, 1, 2]).unwrap();
+
+		let result = TestData::without_env(dir.path()).run_bytes_to_result(|_| vec![0, 1, 2, 255]);
+		assert!(result.tests[0].success);
+		assert!(!result.tests[1].success);
+
+		let item = &result.tests[1];
+		assert_eq!(item.actual.len(), 1);
+		assert!(item.actual[0].starts_with("00000000  00 01 02 ff "));
+		assert!(item.expect.as_ref().unwrap()[0].starts_with("00000000  00 01 02 "));
+	}
+
+	#[test]
+	fn bytes_runs_callback_with_file_bytes() {
+		let dir = temp_dir();
+		std::fs::write(dir.path().join("test.input"), [0xfe, 0xff]).unwrap();
+		std::fs::write(dir.path().join("test.valid"), [0xff, 0xfe]).unwrap();
+		TestData::without_env(dir.path()).run_bytes(|mut input| {
+			input.reverse();
+			input
+		});
+	}
+
+	//------------------------------------------------------------------------//
+	// Placeholders
+	//------------------------------------------------------------------------//
+
+	#[test]
+	fn placeholders_match_output() {
+		let dir = temp_dir();
+		helper::write_case(
+			&dir,
+			"a.input",
+			"started at 12:34\nlog 1\nlog 2\ncommit 0a1b2c",
+			"started at {{*}}\n{{...}}\ncommit {{re:[0-9a-f]{6}}}",
+		);
+		TestData::without_env(dir.path()).run(|input| input);
+	}
+
+	#[test]
+	fn placeholders_show_diff_against_literal_text() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "value: abc", "value: {{re:[0-9]+}}");
+
+		let result = testdata_to_result(dir.path(), |input| input);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		assert_eq!(item.expect, Some(vec!["value: {{re:[0-9]+}}".to_string()]));
+		assert_eq!(item.actual, ["value: abc"]);
+	}
+
+	#[test]
+	fn placeholders_report_invalid_pattern() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "{{re:(}}");
+
+		let result = testdata_to_result(dir.path(), |input| input);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		let unexpected = item.unexpected.as_deref().unwrap();
+		assert!(
+			unexpected.starts_with("has an invalid placeholder in `a.valid` at line 1:"),
+			"unexpected message: {}",
+			unexpected
+		);
+
+		// the diff is still shown against the literal text
+		let diff = item.diff().unwrap();
+		assert!(diff.contains("-a\n+{{re:(}}"), "unexpected diff: {}", diff);
+	}
+
+	#[test]
+	fn placeholders_can_be_escaped() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "{{x}} 1", "\\{{x}} {{*}}");
+		TestData::without_env(dir.path()).run(|input| input);
+	}
+
+	#[test]
+	fn placeholders_escaped_only_lines_are_literal() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "{{x}}", "\\{{x}}");
+		helper::write_case(&dir, "b.input", "{{x}}", "\\{{re:(}}");
+
+		let result = testdata_to_result(dir.path(), |input| input);
+		assert!(result.tests[0].success);
+		let item = &result.tests[1];
+		assert!(!item.success);
+		assert_eq!(item.unexpected, None);
+	}
+
+	#[test]
+	fn placeholders_are_not_used_for_exact_comparison() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "abc", "{{*}}");
+
+		let result = TestData::without_env(dir.path()).run_raw_to_result(|input| input);
+		assert!(!result.success());
+	}
+
+	#[test]
+	fn placeholders_match_section_output() {
+		let dir = temp_dir();
+		dir.create_file("a.test", "-- input --\n1\n2\n-- output --\n{{re:\\d}}\n1");
+
+		let result = TestData::without_env(dir.path()).run_sections_to_result(reverse);
+		assert!(result.success());
+	}
+
+	//------------------------------------------------------------------------//
+	// Redactions
+	//------------------------------------------------------------------------//
+
+	#[test]
+	#[cfg(feature = "redact")]
+	fn redactions_apply_before_comparing() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "pid 123", "pid [PID]\ntook [DURATION]");
+
+		TestData::without_env(dir.path())
+			.redact(Redactions::builtin().rule("pid", r"pid \d+", "pid [PID]"))
+			.run(|mut input| {
+				input.push("took 15ms".to_string());
+				input
+			});
+	}
+
+	#[test]
+	#[cfg(feature = "redact")]
+	fn redactions_apply_to_temp_paths() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "file.txt", "[TEMP]/file.txt");
+
+		let path = dir.path().to_owned();
+		TestData::without_env(dir.path())
+			.redact(Redactions::new().paths())
+			.run(|input| vec![format!("{}/{}", path.to_string_lossy(), input[0])]);
+	}
+
+	#[test]
+	#[cfg(feature = "redact")]
+	fn redactions_bound_to_the_thread_apply_to_testdata() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "took 15ms", "took [DURATION]");
+		dir.create_file("b.test", "-- input --\n1.5s\n-- output --\n[DURATION]");
+
+		Redactions::builtin().bind(|| {
+			TestData::without_env(dir.path()).run(|input| input);
+			TestData::without_env(dir.path()).run_sections(|input| input);
+			TestData::without_env(dir.path()).run_parallel(|input| input);
+		});
+	}
+
+	#[test]
+	#[cfg(feature = "redact")]
+	fn redactions_apply_to_joined_section_output() {
+		let dir = temp_dir();
+		dir.create_file("a.test", "-- input --\nbegin\nend\n-- output --\n[BLOCK]");
+
+		TestData::without_env(dir.path())
+			.redact(Redactions::new().rule("block", r"begin\nend", "[BLOCK]"))
+			.run_sections(|input| input);
+	}
+
+	#[test]
+	#[cfg(feature = "redact")]
+	fn redactions_apply_to_written_dirs() {
+		let dir = temp_dir();
+		dir.create_file("new/input/a.txt", "took 15ms");
+		dir.create_file("bless/input/a.txt", "took 15ms");
+		dir.create_file("bless/expected/a.txt", "old");
+
+		let read = |file: &str| std::fs::read_to_string(dir.path().join(file)).unwrap();
+		let data = TestData::without_env(dir.path()).redact(Redactions::builtin());
+		let result = data.clone().run_dirs_to_result(|_| {});
+		assert!(!result.success());
+		assert_eq!(read("new/expected.new/a.txt"), "took [DURATION]");
+
+		let result = data.bless(true).run_dirs_to_result(|_| {});
+		assert!(result.success());
+		assert_eq!(read("new/expected/a.txt"), "took [DURATION]");
+		assert_eq!(read("bless/expected/a.txt"), "took [DURATION]");
+	}
+
+	#[test]
+	#[cfg(feature = "redact")]
+	fn redactions_are_not_applied_to_bytes() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "took 15ms", "took [DURATION]");
+
+		let result = TestData::without_env(dir.path())
+			.redact(Redactions::builtin())
+			.run_bytes_to_result(|input| input.to_vec());
+		assert!(!result.success());
+	}
+
+	//------------------------------------------------------------------------//
+	// Stale files
+	//------------------------------------------------------------------------//
+
+	#[test]
+	fn stale_files_report_orphaned_files_as_warnings() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		dir.create_file("b.valid", "b");
+		dir.create_file("c.valid.new", "c");
+		dir.create_file("sub/d.error", "d");
+		dir.create_file("sub/d.txt", "d");
+
+		let result = testdata_to_result(dir.path(), |input| input);
+		assert!(result.success());
+		assert_eq!(
+			result.warnings,
+			[
+				"`b.valid` is orphaned, since `b.input` does not exist",
+				"`c.valid.new` is orphaned, since `c.input` does not exist",
+				"`sub/d.error` is orphaned, since `sub/d.input` does not exist",
+			]
+		);
+	}
+
+	#[test]
+	fn stale_files_report_leftover_new_files() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "old a");
+		dir.create_file("a.valid.new", "a");
+		dir.create_file("b.input", "b");
+
+		let result = testdata_to_result(dir.path(), |input| input);
+		assert!(!result.success());
+		assert_eq!(
+			result.warnings,
+			["`a.valid.new` is stale, since `a.valid` exists"]
+		);
+
+		// the new file generated by the run is not stale
+		assert!(dir.path().join("b.valid.new").exists());
+	}
+
+	#[test]
+	fn stale_files_fail_in_strict_mode() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		dir.create_file("b.valid", "b");
+
+		let result = TestData::without_env(dir.path())
+			.strict(true)
+			.run_to_result(|input| input);
+		assert!(!result.success());
+		assert!(result.warnings.is_empty());
+
+		let item = &result.tests[1];
+		assert_eq!(item.name, "b.valid");
+		assert_eq!(
+			item.message().unwrap(),
+			"is orphaned, since `b.input` does not exist"
+		);
+
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in TestData::without_env(&path).strict(true).run(|input| input));
+	}
+
+	#[test]
+	fn stale_files_use_configured_extensions() {
+		let dir = temp_dir();
+		dir.create_file("a.in", "a");
+		dir.create_file("a.out", "a");
+		dir.create_file("b.out", "b");
+		dir.create_file("c.valid", "c");
+
+		let result = TestData::without_env(dir.path())
+			.input_extension("in")
+			.valid_extension("out")
+			.run_to_result(|input| input);
+		assert_eq!(
+			result.warnings,
+			["`b.out` is orphaned, since `b.in` does not exist"]
+		);
+	}
+
+	//------------------------------------------------------------------------//
+	// Timeouts
+	//------------------------------------------------------------------------//
+
+	#[test]
+	fn timeout_does_not_affect_cases_that_finish_in_time() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "b.input", "// tux: timeout=10s\nb", "b");
+
+		TestData::without_env(dir.path())
+			.timeout(Duration::from_secs(10))
+			.run(|input| input);
+	}
+
+	#[test]
+	fn timeout_fails_slow_case_and_continues() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "b.input", "slow", "slow");
+		helper::write_case(&dir, "c.input", "// tux: timeout=20ms\nslow", "slow");
+		helper::write_case(&dir, "d.input", "d", "d");
+
+		let result = TestData::without_env(dir.path())
+			.timeout(Duration::from_millis(50))
+			.run_to_result(helper::slow_on_input);
+		let messages = result.tests.iter().map(|x| x.message()).collect::<Vec<_>>();
+		assert_eq!(
+			messages,
+			[
+				None,
+				Some("timed out after 50ms".to_string()),
+				Some("timed out after 20ms".to_string()),
+				None,
+			]
+		);
+		assert!(!dir.path().join("b.valid.new").exists());
+	}
+
+	#[test]
+	fn timeout_fails_slow_sections_and_dirs() {
+		let dir = temp_dir();
+		dir.create_file(
+			"a.test",
+			"-- input --\n// tux: timeout=20ms\nslow\n-- output --\nslow",
+		);
+		dir.create_file("case/input/a.txt", "a");
+		dir.create_file("case/expected/a.txt", "a");
+
+		let result =
+			TestData::without_env(dir.path()).run_sections_to_result(helper::slow_on_input);
+		assert_eq!(result.tests[0].message().unwrap(), "timed out after 20ms");
+
+		let result = TestData::without_env(dir.path())
+			.timeout(Duration::from_millis(20))
+			.run_dirs_to_result(|_| std::thread::sleep(Duration::from_millis(100)));
+		assert_eq!(result.tests[0].message().unwrap(), "timed out after 20ms");
+	}
+
+	#[test]
+	fn isolated_fails_stuck_case_and_continues() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "b.input", "hang", "hang");
+		helper::write_case(&dir, "c.input", "c", "c");
+
+		let result = TestData::without_env(dir.path())
+			.timeout(Duration::from_millis(50))
+			.run_isolated_to_result(helper::hang_on_input);
+		assert!(!result.success());
+		assert!(result.tests[0].success);
+		assert!(result.tests[2].success);
+
+		let item = &result.tests[1];
+		assert_eq!(item.name, "b.input");
+		assert_eq!(item.message().unwrap(), "timed out after 50ms");
+	}
+
+	#[test]
+	fn isolated_uses_timeout_from_directive() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "// tux: timeout=20ms\nhang", "hang");
+
+		let result =
+			TestData::without_env(dir.path()).run_isolated_to_result(helper::hang_on_input);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		assert_eq!(item.message().unwrap(), "timed out after 20ms");
+	}
+
+	#[test]
+	fn isolated_reports_panics_and_output() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "b.input", "b", "c");
+		helper::write_case(&dir, "panic.input", "panic", "panic");
+
+		let result = TestData::without_env(dir.path()).run_isolated_to_result(|input| {
+			if input[0] == "panic" {
+				panic!("callback panicked");
+			}
+			input
+		});
+		assert!(result.tests[0].success);
+		assert_eq!(result.tests[1].expect, Some(vec!["c".to_string()]));
+		let panic = result.tests[2].panic.as_deref().unwrap();
+		assert!(
+			panic.starts_with("callback panicked (at "),
+			"panic: {}",
+			panic
+		);
+	}
+
+	//------------------------------------------------------------------------//
+	// Checks
+	//------------------------------------------------------------------------//
+
+	#[test]
+	fn checks_from_check_file_replace_valid_file() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "one\ntwo\nthree");
+		dir.create_file("a.check", "CHECK: one\nCHECK-NEXT: two\nCHECK-NOT: four");
+
+		let result = testdata_to_result(dir.path(), |input| input);
+		assert!(result.success());
+		assert_eq!(result.tests[0].valid_file, "a.check");
+		assert!(!dir.path().join("a.valid.new").exists());
+	}
+
+	#[test]
+	fn checks_from_input_comments() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "x = 1\n// CHECK: x = 1\n// CHECK-NOT: //");
+		TestData::without_env(dir.path())
+			.input_checks(true)
+			.run(|input| input.into_iter().filter(|x| !x.starts_with("//")).collect());
+	}
+
+	#[test]
+	fn checks_from_input_comments_are_disabled_by_default() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "# CHECK-OUT: x", "# CHECK-OUT: x");
+		TestData::without_env(dir.path()).run(|input| input);
+	}
+
+	#[test]
+	fn checks_do_not_replace_existing_valid_file() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "x\n// CHECK: x", "other");
+
+		let result = TestData::without_env(dir.path())
+			.input_checks(true)
+			.run_to_result(|input| input);
+		assert!(!result.success());
+		assert_eq!(result.tests[0].valid_file, "a.valid");
+
+		dir.create_file("a.check", "CHECK: x");
+		let result = testdata_to_result(dir.path(), |input| input);
+		assert_eq!(
+			result.tests[0].unexpected.as_deref(),
+			Some("has both `a.valid` and `a.check`, only one is expected")
+		);
+	}
+
+	#[test]
+	fn checks_report_failed_directive() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "one\ntwo");
+		dir.create_file("a.check", "CHECK: one\nCHECK-NEXT: three");
+
+		let result = testdata_to_result(dir.path(), |input| input);
+		let item = &result.tests[0];
+		assert!(!item.success);
+		assert_eq!(
+			item.unexpected.as_deref(),
+			Some("failed `CHECK-NEXT: three` at `a.check:2`, did not match output line 2")
+		);
+		assert_eq!(item.actual, ["one", "two"]);
+
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in TestData::without_env(&path).run(|input| input));
+	}
+
+	#[test]
+	fn checks_fail_for_check_file_without_directives() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "a");
+		dir.create_file("a.check", "nothing to check");
+
+		let result = testdata_to_result(dir.path(), |input| input);
+		assert_eq!(
+			result.tests[0].unexpected.as_deref(),
+			Some("has no check directives in `a.check`")
+		);
+	}
+
+	//------------------------------------------------------------------------//
+	// Directories
+	//------------------------------------------------------------------------//
+
+	fn append_line(dir: &TempDir) {
+		let file = dir.path().join("a.txt");
+		let text = std::fs::read_to_string(&file).unwrap();
+		std::fs::write(&file, format!("{}\nappended", text)).unwrap();
+	}
+
+	#[test]
+	fn dirs_run_each_case_with_input_tree() {
+		let dir = temp_dir();
+		dir.create_file("one/input/a.txt", "1");
+		dir.create_file("one/expected/a.txt", "1\nappended");
+		dir.create_file("sub/two/input/a.txt", "2");
+		dir.create_file("sub/two/input/b/c.txt", "c");
+		dir.create_file("sub/two/expected/a.txt", "2\nappended");
+		dir.create_file("sub/two/expected/b/c.txt", "c");
+
+		let mut inputs = Vec::new();
+		let result = TestData::without_env(dir.path()).run_dirs_to_result(|case| {
+			inputs.push(std::fs::read_to_string(case.path().join("a.txt")).unwrap());
+			append_line(case);
+		});
+		assert!(result.success());
+		assert_eq!(inputs, ["1", "2"]);
+
+		let names = result
+			.tests
+			.iter()
+			.map(|x| x.name.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(names, ["one", "sub/two"]);
+
+		// the input tree is not modified
+		let input = std::fs::read_to_string(dir.path().join("one/input/a.txt")).unwrap();
+		assert_eq!(input, "1");
+	}
+
+	#[test]
+	fn dirs_report_mismatched_files() {
+		let dir = temp_dir();
+		dir.create_file("case/input/a.txt", "a");
+		dir.create_file("case/input/extra.txt", "extra");
+		dir.create_file("case/expected/a.txt", "a\nchanged");
+		dir.create_file("case/expected/missing.txt", "missing");
+
+		let result = TestData::without_env(dir.path()).run_dirs_to_result(append_line);
+		let item = &result.tests[0];
+		assert!(!item.success);
+
+		let files = item
+			.files
+			.iter()
+			.map(|x| (x.path.as_str(), x.actual.is_some(), x.expect.is_some()))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			files,
+			[
+				("a.txt", true, true),
+				("extra.txt", true, false),
+				("missing.txt", false, true),
+			]
+		);
+		assert_eq!(
+			item.files[0].actual,
+			Some(vec!["a".into(), "appended".into()])
+		);
+		assert_eq!(
+			item.files[0].expect,
+			Some(vec!["a".into(), "changed".into()])
+		);
+
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in TestData::without_env(&path).run_dirs(append_line));
+	}
+
+	#[test]
+	fn dirs_compare_binary_files_exactly() {
+		let dir = temp_dir();
+		dir.create_file("case/input/a.bin", [0xFF, 0x00]);
+		dir.create_file("case/expected/a.bin", [0xFF, 0x01]);
+
+		let result = TestData::without_env(dir.path()).run_dirs_to_result(|_| {});
+		assert!(!result.success());
+		assert_eq!(result.tests[0].files[0].path, "a.bin");
+	}
+
+	#[test]
+	fn dirs_create_new_expected_tree_if_missing() {
+		let dir = temp_dir();
+		dir.create_file("case/input/a.txt", "a");
+
+		let result = TestData::without_env(dir.path()).run_dirs_to_result(append_line);
+		assert!(!result.success());
+
+		let new_file = dir.path().join("case/expected.new/a.txt");
+		assert_eq!(std::fs::read_to_string(new_file).unwrap(), "a\nappended");
+		assert!(!dir.path().join("case/expected").exists());
+	}
+
+	#[test]
+	fn dirs_bless_replaces_expected_tree() {
+		let dir = temp_dir();
+		dir.create_file("case/input/a.txt", "a");
+		dir.create_file("case/expected/a.txt", "old");
+		dir.create_file("case/expected/old.txt", "old");
+		dir.create_file("case/expected.new/a.txt", "stale");
+
+		let result = TestData::without_env(dir.path())
+			.bless(true)
+			.run_dirs_to_result(append_line);
+		assert!(result.success());
+		assert!(result.tests[0].blessed);
+
+		let expected = dir.path().join("case/expected");
+		let text = std::fs::read_to_string(expected.join("a.txt")).unwrap();
+		assert_eq!(text, "a\nappended");
+		assert!(!expected.join("old.txt").exists());
+		assert!(!dir.path().join("case/expected.new").exists());
+	}
+
+	#[test]
+	fn dirs_report_panics_in_callback() {
+		let dir = temp_dir();
+		dir.create_file("case/input/a.txt", "a");
+		dir.create_file("case/expected/a.txt", "a");
+
+		let result = TestData::without_env(dir.path()).run_dirs_to_result(|_| panic!("dir panic"));
+		assert!(!result.success());
+		assert!(result.tests[0]
+			.panic
+			.as_ref()
+			.unwrap()
+			.contains("dir panic"));
+	}
+
+	//------------------------------------------------------------------------//
+	// TestDataResult
+	//------------------------------------------------------------------------//
+
+	#[test]
+	fn to_result_returns_ok_for_valid_case() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "test.input", "abc\n123", "123\nabc");
+
+		let result = testdata_to_result(dir.path(), |mut input| {
+			input.reverse();
+			input
+		});
+
+		assert!(result.success());
+		assert_eq!(result.tests.len(), 1);
+		assert_eq!(result.tests[0].name, "test.input");
+		assert!(result.tests[0].success);
+	}
+
+	#[test]
+	fn to_result_returns_an_item_for_each_case() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "A", "a");
+		helper::write_case(&dir, "b.input", "B", "b");
+		helper::write_case(&dir, "sub/some.input", "Some", "some");
+
+		let result = testdata_to_result(dir.path(), |input| {
+			input.into_iter().map(|x| x.to_lowercase()).collect()
+		});
+
+		assert_eq!(result.tests.len(), 3);
+		assert_eq!(result.tests[0].name, "a.input");
+		assert_eq!(result.tests[1].name, "b.input");
+		assert_eq!(result.tests[2].name, "sub/some.input");
+	}
+
+	#[test]
+	fn to_result_fails_if_output_does_not_match() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "Valid 1", "valid 1");
+		helper::write_case(&dir, "b.input", "Valid 2", "valid 2");
+		helper::write_case(
+			&dir,
+			"c.input",
+			"this should fail",
+			"invalid output for the test",
+		);
+
+		let result = testdata_to_result(dir.path(), |input| {
+			input.into_iter().map(|x| x.to_lowercase()).collect()
+		});
+
+		assert!(!result.success());
+		assert!(result.tests.len() == 3);
+		assert!(result.tests[0].success);
+		assert!(result.tests[1].success);
+		assert!(!result.tests[2].success);
+	}
+
+	#[test]
+	fn result_reports_status_and_diff() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "b.input", "b", "fail");
+		dir.create_file("c.input", "// tux: skip\nc");
+
+		let result = TestData::without_env(dir.path()).run_to_result(|input| input);
+		let status = result
+			.tests
+			.iter()
+			.map(|x| x.status().as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(status, ["passed", "failed", "skipped"]);
+
+		let item = &result.tests[1];
+		assert_eq!(
+			item.message().as_deref(),
+			Some("output did not match `b.valid`")
+		);
+		assert_eq!(
+			item.diff().as_deref(),
+			Some("--- b.input\n+++ b.valid\n@@ -1 +1 @@\n-b\n+fail")
+		);
+		assert_eq!(result.tests[0].message(), None);
+		assert_eq!(result.tests[0].diff(), None);
+	}
+
+	#[test]
+	fn result_writes_json_and_junit_reports() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "cases/a.input", "a", "a");
+		helper::write_case(&dir, "cases/b.input", "b", "<fail>");
+
+		let result = TestData::without_env(dir.path().join("cases")).run_to_result(|input| input);
+		let reports = dir.path().join("reports");
+		result.write_reports(&reports);
+
+		let file_name = super::report::report_file_name(&result.name);
+		let json = std::fs::read_to_string(reports.join(format!("{}.json", file_name))).unwrap();
+		assert!(json.contains("\"tests\": 2,"));
+		assert!(json.contains("\"failures\": 1,"));
+		assert!(json.contains("\"name\": \"b.input\",\n      \"status\": \"failed\""));
+		assert!(json.contains("\"diff\": \"--- b.input\\n+++ b.valid\\n"));
+
+		let xml = std::fs::read_to_string(reports.join(format!("{}.xml", file_name))).unwrap();
+		assert!(xml.contains("tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"0\""));
+		assert!(xml.contains("<testcase name=\"a.input\""));
+		assert!(xml.contains("<failure message=\"output did not match `b.valid`\">"));
+		assert!(xml.contains("+&lt;fail&gt;"));
+	}
+
+	#[test]
+	fn result_reports_count_filtered_cases_as_skipped() {
+		let dir = temp_dir();
+		helper::write_case(&dir, "a.input", "a", "a");
+		helper::write_case(&dir, "a_skip.input", "// tux: skip\nb", "b");
+		helper::write_case(&dir, "c.input", "c", "c");
+
+		let result = TestData::without_env(dir.path())
+			.filter("a")
+			.run_to_result(|input| input);
+		assert_eq!(result.skipped, ["c.input"]);
+
+		let json = result.to_json();
+		assert!(json.contains("\"tests\": 3,"), "JSON: {}", json);
+		assert!(json.contains("\"skipped\": 2,"), "JSON: {}", json);
+		assert!(json.contains(
+			"\"name\": \"c.input\",\n      \"status\": \"skipped\",\n      \"duration\": 0.000000,\n      \"message\": \"not matching filter `a`\""
+		));
+
+		let xml = result.to_junit_xml();
+		assert!(xml.contains("tests=\"3\" failures=\"0\" errors=\"0\" skipped=\"2\""));
+		assert!(xml.contains("<skipped message=\"not matching filter `a`\"/>"));
+	}
+
+	//------------------------------------------------------------------------//
+	// Helper code
+	//------------------------------------------------------------------------//
+
+	mod helper {
+		use super::*;
+
+		pub fn write_case(dir: &TempDir, input_file: &str, input: &str, expected: &str) {
+			dir.create_file(input_file, input);
+
+			let suffix = ".input";
+			let basename = input_file.strip_suffix(suffix).unwrap();
+			dir.create_file(&format!("{}.valid", basename), expected);
+		}
+
+		/// Returns the input, except for an input of `hang`, which never
+		/// returns.
+		pub fn hang_on_input(input: Vec<String>) -> Vec<String> {
+			if input == ["hang"] {
+				loop {
+					std::thread::sleep(std::time::Duration::from_millis(100));
+				}
+			}
+			input
+		}
+
+		/// Returns the input, after a delay for an input of `slow`.
+		pub fn slow_on_input(input: Vec<String>) -> Vec<String> {
+			if input == ["slow"] {
+				std::thread::sleep(std::time::Duration::from_millis(100));
+			}
+			input
+		}
+	}
+}
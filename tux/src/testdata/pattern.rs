@@ -0,0 +1,300 @@
+use std::borrow::Cow;
+
+use regex::Regex;
+
+const PLACEHOLDER_START: &str = "{{";
+const PLACEHOLDER_END: &str = "}}";
+const ANY_TEXT: &str = "*";
+const ANY_LINES: &str = "...";
+const REGEX_PREFIX: &str = "re:";
+const ESCAPE: &str = "\\";
+
+/// Line from an expected output with placeholders.
+enum PatternLine<'a> {
+	/// Line without placeholders, which must match exactly. Escaped `\{{`
+	/// are already replaced by `{{`.
+	Literal(Cow<'a, str>),
+	/// Line with placeholders, converted to an anchored regex.
+	Regex(Regex),
+	/// The `{{...}}` placeholder, matching any number of lines.
+	AnyLines,
+}
+
+/// Returns `true` if the expected lines contain any `{{`, either as a
+/// placeholder or escaped, and so must be compared as patterns.
+pub(super) fn has_placeholders<S: AsRef<str>>(expected: &[S]) -> bool {
+	expected
+		.iter()
+		.any(|x| x.as_ref().contains(PLACEHOLDER_START))
+}
+
+/// Returns `true` if the actual lines match the expected lines, considering
+/// the placeholders in the expected lines:
+///
+/// - `{{*}}` matches any text within a line;
+/// - `{{re:REGEX}}` matches the regular expression within a line;
+/// - a line with only `{{...}}` matches any number of lines.
+///
+/// A `{{` preceded by a backslash (`\{{`) is a literal `{{`. Any other text
+/// must match exactly. Returns an error if a placeholder is invalid.
+pub(super) fn matches_placeholders<S1, S2>(actual: &[S1], expected: &[S2]) -> Result<bool, String>
+where
+	S1: AsRef<str>,
+	S2: AsRef<str>,
+{
+	let mut pattern = Vec::new();
+	for (index, line) in expected.iter().enumerate() {
+		let line =
+			parse_line(line.as_ref()).map_err(|err| format!("line {}: {}", index + 1, err))?;
+		pattern.push(line);
+	}
+	Ok(matches_lines(actual, &pattern))
+}
+
+fn parse_line(line: &str) -> Result<PatternLine<'_>, String> {
+	if !line.contains(PLACEHOLDER_START) {
+		return Ok(PatternLine::Literal(Cow::Borrowed(line)));
+	}
+
+	if !has_unescaped_placeholder(line) {
+		let escaped = format!("{}{}", ESCAPE, PLACEHOLDER_START);
+		return Ok(PatternLine::Literal(Cow::Owned(
+			line.replace(&escaped, PLACEHOLDER_START),
+		)));
+	}
+
+	if line.trim() == format!("{}{}{}", PLACEHOLDER_START, ANY_LINES, PLACEHOLDER_END) {
+		return Ok(PatternLine::AnyLines);
+	}
+
+	let regex = format!("^{}$", placeholder_regex(line)?);
+	Regex::new(&regex)
+		.map(PatternLine::Regex)
+		.map_err(|err| err.to_string())
+}
+
+/// Returns `true` if the line has a `{{` that is not escaped as `\{{`.
+fn has_unescaped_placeholder(line: &str) -> bool {
+	line.match_indices(PLACEHOLDER_START)
+		.any(|(start, _)| !line[..start].ends_with(ESCAPE))
+}
+
+/// Pattern matching part of a line, with the same placeholders as an
+/// expected line, except for `{{...}}`.
+#[derive(Debug)]
+pub(super) struct TextPattern(Regex);
+
+impl TextPattern {
+	pub fn new(text: &str) -> Result<Self, String> {
+		let regex = placeholder_regex(text)?;
+		Regex::new(&regex)
+			.map(TextPattern)
+			.map_err(|err| err.to_string())
+	}
+
+	/// Returns the byte range of the first match in the text, if any.
+	pub fn find(&self, text: &str) -> Option<std::ops::Range<usize>> {
+		self.0.find(text).map(|x| x.range())
+	}
+}
+
+/// Returns the regex for the text with placeholders, without anchors.
+fn placeholder_regex(line: &str) -> Result<String, String> {
+	let mut regex = String::new();
+	let mut text = line;
+	while let Some(start) = text.find(PLACEHOLDER_START) {
+		// `\{{` is a literal `{{`
+		if let Some(literal) = text[..start].strip_suffix(ESCAPE) {
+			regex.push_str(&regex::escape(literal));
+			regex.push_str(&regex::escape(PLACEHOLDER_START));
+			text = &text[start + PLACEHOLDER_START.len()..];
+			continue;
+		}
+
+		regex.push_str(&regex::escape(&text[..start]));
+
+		let placeholder = &text[start + PLACEHOLDER_START.len()..];
+		let end = placeholder_end(placeholder)
+			.ok_or_else(|| format!("unclosed `{}` in `{}`", PLACEHOLDER_START, line))?;
+		let inner = &placeholder[..end];
+		if inner == ANY_TEXT {
+			regex.push_str(".*");
+		} else if let Some(inner) = inner.strip_prefix(REGEX_PREFIX) {
+			regex.push_str(&format!("(?:{})", inner));
+		} else {
+			return Err(format!(
+				"unknown placeholder `{}{}{}`",
+				PLACEHOLDER_START, inner, PLACEHOLDER_END
+			));
+		}
+		text = &placeholder[end + PLACEHOLDER_END.len()..];
+	}
+	regex.push_str(&regex::escape(text));
+	Ok(regex)
+}
+
+/// Returns the end of the placeholder text, that is, the first `}}` that is
+/// not part of a regex repetition like `{40}` or escaped with a backslash.
+fn placeholder_end(text: &str) -> Option<usize> {
+	let mut depth = 0;
+	let mut chars = text.char_indices();
+	while let Some((index, c)) = chars.next() {
+		match c {
+			'\\' => {
+				chars.next();
+			}
+			'{' => depth += 1,
+			'}' if depth > 0 => depth -= 1,
+			'}' if text[index..].starts_with(PLACEHOLDER_END) => return Some(index),
+			_ => {}
+		}
+	}
+	None
+}
+
+/// Matches the lines between the `{{...}}` lines as segments. The first and
+/// last segments must match at the start and end of the output, and each
+/// other segment matches at its first occurrence after the previous one,
+/// which leaves the most lines for the remaining segments. This uses
+/// constant memory, and only backtracks when looking for a segment.
+fn matches_lines<S: AsRef<str>>(actual: &[S], pattern: &[PatternLine]) -> bool {
+	let mut segments = pattern.split(|x| matches!(x, PatternLine::AnyLines));
+	let first = segments.next().unwrap_or_default();
+	let Some(last) = segments.next_back() else {
+		return actual.len() == first.len() && matches_at(actual, 0, first);
+	};
+
+	if first.len() + last.len() > actual.len()
+		|| !matches_at(actual, 0, first)
+		|| !matches_at(actual, actual.len() - last.len(), last)
+	{
+		return false;
+	}
+
+	let mut start = first.len();
+	let end = actual.len() - last.len();
+	for segment in segments {
+		if start + segment.len() > end {
+			return false;
+		}
+		match (start..=end - segment.len()).find(|&x| matches_at(actual, x, segment)) {
+			Some(found) => start = found + segment.len(),
+			None => return false,
+		}
+	}
+	true
+}
+
+/// Returns `true` if the pattern lines match the actual lines at the given
+/// start, which must have enough lines left.
+fn matches_at<S: AsRef<str>>(actual: &[S], start: usize, pattern: &[PatternLine]) -> bool {
+	pattern
+		.iter()
+		.zip(&actual[start..])
+		.all(|(line, actual)| matches_line(actual.as_ref(), line))
+}
+
+fn matches_line(actual: &str, pattern: &PatternLine) -> bool {
+	match pattern {
+		PatternLine::Literal(expected) => actual == expected,
+		PatternLine::Regex(regex) => regex.is_match(actual),
+		PatternLine::AnyLines => true,
+	}
+}
+
+#[cfg(test)]
+mod test_pattern {
+	use super::matches_placeholders;
+
+	fn matches(actual: &[&str], expected: &[&str]) -> bool {
+		matches_placeholders(actual, expected).unwrap()
+	}
+
+	#[test]
+	fn matches_literal_lines() {
+		assert!(matches(&["a", "b"], &["a", "b"]));
+		assert!(!matches(&["a", "b"], &["a", "c"]));
+		assert!(!matches(&["a"], &["a", "b"]));
+	}
+
+	#[test]
+	fn matches_any_text() {
+		assert!(matches(&["took 12ms"], &["took {{*}}"]));
+		assert!(matches(&["at /tmp/x/a.txt:1"], &["at {{*}}/a.txt:{{*}}"]));
+		assert!(!matches(&["at /tmp/x/b.txt:1"], &["at {{*}}/a.txt:{{*}}"]));
+	}
+
+	#[test]
+	fn escapes_literal_text_around_placeholders() {
+		assert!(matches(&["(a.b) 1"], &["(a.b) {{*}}"]));
+		assert!(!matches(&["(axb) 1"], &["(a.b) {{*}}"]));
+	}
+
+	#[test]
+	fn matches_regex() {
+		let hash = "0123456789abcdef0123456789abcdef01234567";
+		let line = format!("commit {}", hash);
+		assert!(matches(&[&line], &["commit {{re:[0-9a-f]{40}}}"]));
+		assert!(!matches(&["commit 123"], &["commit {{re:[0-9a-f]{40}}}"]));
+		assert!(matches(&["a}b"], &["{{re:a\\}b}}"]));
+	}
+
+	#[test]
+	fn matches_any_lines() {
+		let expected = ["start", "{{...}}", "end"];
+		assert!(matches(&["start", "end"], &expected));
+		assert!(matches(&["start", "1", "2", "end"], &expected));
+		assert!(!matches(&["start", "1", "2"], &expected));
+		assert!(matches(&["a", "b"], &["{{...}}"]));
+		assert!(matches(&["a", "x", "b", "x"], &["{{...}}", "b", "{{...}}"]));
+	}
+
+	#[test]
+	fn matches_any_lines_with_backtracking() {
+		let expected = ["{{...}}", "a", "b", "{{...}}", "c"];
+		assert!(matches(&["a", "a", "b", "c"], &expected));
+		assert!(matches(&["x", "a", "b", "a", "b", "c", "c"], &expected));
+		assert!(!matches(&["a", "c", "b", "c"], &expected));
+		assert!(matches(&["a"], &["a", "{{...}}", "{{...}}"]));
+		assert!(!matches(&[] as &[&str], &["a", "{{...}}"]));
+	}
+
+	#[test]
+	fn matches_long_output_with_any_lines() {
+		let actual = vec!["line"; 100_000];
+		let mut expected = vec!["line"; 50_000];
+		expected.insert(1, "{{...}}");
+		assert!(matches(&actual, &expected));
+	}
+
+	#[test]
+	fn matches_escaped_placeholder_start() {
+		assert!(matches(&["{{x}} 1"], &["\\{{x}} {{*}}"]));
+		assert!(matches(&["a {{"], &["a \\{{"]));
+		assert!(!matches(&["\\{{x}}"], &["\\{{x}}"]));
+		assert!(matches(&["{{x}}"], &["{{re:\\{\\{x\\}\\}}}"]));
+	}
+
+	#[test]
+	fn matches_escaped_only_lines_as_literal() {
+		assert!(matches(&["{{x}} and {{y"], &["\\{{x}} and \\{{y"]));
+		assert!(!matches(&["{{x}}"], &["\\{{y}}"]));
+
+		// no placeholder errors for text that only looks like placeholders
+		assert_eq!(matches_placeholders(&["a"], &["\\{{re:(}}"]), Ok(false));
+		assert_eq!(matches_placeholders(&["a"], &["\\{{x"]), Ok(false));
+		assert!(matches(&["{{re:(}}"], &["\\{{re:(}}"]));
+	}
+
+	#[test]
+	fn reports_invalid_placeholders() {
+		let err = matches_placeholders(&["a"], &["a", "{{re:(}}"]).unwrap_err();
+		assert!(err.starts_with("line 2: regex parse error"));
+
+		let err = matches_placeholders(&["a"], &["{{x}}"]).unwrap_err();
+		assert!(err.contains("unknown placeholder `{{x}}`"));
+
+		let err = matches_placeholders(&["a"], &["{{*"]).unwrap_err();
+		assert!(err.contains("unclosed `{{`"));
+	}
+}
@@ -0,0 +1,332 @@
+use std::{path::Path, time::Duration};
+
+use super::{TestDataResult, TestDataResultItem, TestDataStatus};
+
+/// Environment variable with a directory where [`testdata`](super::testdata)
+/// writes a JSON and a JUnit XML report for each run.
+///
+/// See [`TestDataResult::write_reports`].
+pub const TESTDATA_REPORT_DIR_VAR: &str = "TUX_REPORT_DIR";
+
+impl TestDataResult {
+	/// Writes a JSON and a JUnit XML report for the run to the given
+	/// directory, creating it if necessary.
+	///
+	/// The report files are named after the run (e.g. the `tests/testdata`
+	/// run generates `tests_testdata.json` and `tests_testdata.xml`), so
+	/// multiple runs can write to the same directory.
+	///
+	/// This is called for every run when [`TESTDATA_REPORT_DIR_VAR`] is set.
+	pub fn write_reports<P: AsRef<Path>>(&self, dir: P) {
+		let dir = dir.as_ref();
+		std::fs::create_dir_all(dir).expect("creating report directory");
+
+		let file_name = report_file_name(&self.name);
+		let json_path = dir.join(format!("{}.json", file_name));
+		std::fs::write(json_path, self.to_json()).expect("writing JSON report");
+
+		let xml_path = dir.join(format!("{}.xml", file_name));
+		std::fs::write(xml_path, self.to_junit_xml()).expect("writing JUnit report");
+	}
+
+	/// Returns a JSON report for the run, with the name, status, duration in
+	/// seconds, failure message, and diff of each test case, and the warnings
+	/// for the run. Test cases skipped by the filter are included as skipped:
+	///
+	/// ```text
+	/// {
+	///   "name": "tests/testdata",
+	///   "tests": 1,
+	///   "failures": 1,
+	///   "skipped": 0,
+	///   "duration": 0.000120,
+	///   "warnings": [],
+	///   "cases": [
+	///     {
+	///       "name": "a.input",
+	///       "status": "failed",
+	///       "duration": 0.000120,
+	///       "message": "output did not match `a.valid`",
+	///       "diff": "--- a.input\n+++ a.valid\n..."
+	///     }
+	///   ]
+	/// }
+	/// ```
+	///
+	/// The status is one of the [`TestDataStatus`] names.
+	pub fn to_json(&self) -> String {
+		let mut output = String::new();
+		output.push_str("{\n");
+		output.push_str(&format!("  \"name\": {},\n", json_string(&self.name)));
+		output.push_str(&format!("  \"tests\": {},\n", self.test_count()));
+		output.push_str(&format!("  \"failures\": {},\n", self.failed_count()));
+		output.push_str(&format!("  \"skipped\": {},\n", self.skipped_count()));
+		output.push_str(&format!("  \"duration\": {},\n", seconds(self.duration())));
+		let warnings = self.warnings.iter().map(|x| json_string(x));
+		let warnings = warnings.collect::<Vec<_>>().join(", ");
+		output.push_str(&format!("  \"warnings\": [{}],\n", warnings));
+		output.push_str("  \"cases\": [");
+		let mut cases = Vec::new();
+		for it in self.tests.iter() {
+			cases.push(json_case(
+				&it.name,
+				it.status(),
+				it.duration,
+				it.message().as_deref(),
+				it.diff().as_deref(),
+			));
+		}
+		let filter_message = self.filter_message();
+		for it in self.skipped.iter() {
+			cases.push(json_case(
+				it,
+				TestDataStatus::Skipped,
+				Duration::ZERO,
+				Some(&filter_message),
+				None,
+			));
+		}
+		if !cases.is_empty() {
+			output.push('\n');
+			output.push_str(&cases.join(",\n"));
+			output.push_str("\n  ");
+		}
+		output.push_str("]\n}\n");
+		output
+	}
+
+	/// Returns a JUnit XML report for the run, with a single test suite.
+	///
+	/// Failed test cases include the failure message and the diff. Panics
+	/// are reported as errors instead of failures. Blessed test cases and
+	/// expected failures are reported as passed, and test cases skipped by
+	/// the filter are reported as skipped.
+	pub fn to_junit_xml(&self) -> String {
+		let name = xml_escape(&self.name);
+		let errors = self.tests.iter().filter(|x| is_error(x)).count();
+
+		let mut output = String::new();
+		output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+		output.push_str("<testsuites>\n");
+		output.push_str(&format!(
+			"  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{}\">\n",
+			name,
+			self.test_count(),
+			self.failed_count() - errors,
+			errors,
+			self.skipped_count(),
+			seconds(self.duration()),
+		));
+		for it in self.tests.iter() {
+			let case = format!(
+				"    <testcase name=\"{}\" classname=\"{}\" time=\"{}\"",
+				xml_escape(&it.name),
+				name,
+				seconds(it.duration)
+			);
+			match it.status() {
+				TestDataStatus::Failed => {
+					let tag = if is_error(it) { "error" } else { "failure" };
+					let message = it.message().unwrap_or_default();
+					let text = it.diff().unwrap_or_else(|| it.actual.join("\n"));
+					output.push_str(&format!("{}>\n", case));
+					output.push_str(&format!(
+						"      <{} message=\"{}\">{}</{}>\n",
+						tag,
+						xml_escape(&message),
+						xml_escape(&text),
+						tag
+					));
+					output.push_str("    </testcase>\n");
+				}
+				TestDataStatus::Skipped => {
+					let reason = it.skipped.as_deref().unwrap_or_default();
+					output.push_str(&format!("{}>\n", case));
+					output.push_str(&format!(
+						"      <skipped message=\"{}\"/>\n",
+						xml_escape(reason)
+					));
+					output.push_str("    </testcase>\n");
+				}
+				_ => {
+					output.push_str(&format!("{}/>\n", case));
+				}
+			}
+		}
+		let filter_message = xml_escape(&self.filter_message());
+		for it in self.skipped.iter() {
+			output.push_str(&format!(
+				"    <testcase name=\"{}\" classname=\"{}\" time=\"{}\">\n",
+				xml_escape(it),
+				name,
+				seconds(Duration::ZERO)
+			));
+			output.push_str(&format!(
+				"      <skipped message=\"{}\"/>\n",
+				filter_message
+			));
+			output.push_str("    </testcase>\n");
+		}
+		output.push_str("  </testsuite>\n");
+		output.push_str("</testsuites>\n");
+		output
+	}
+
+	fn failed_count(&self) -> usize {
+		self.tests.iter().filter(|x| !x.success).count()
+	}
+
+	/// Returns the number of test cases, including those skipped by the
+	/// filter, which are not part of [`TestDataResult::tests`].
+	fn test_count(&self) -> usize {
+		self.tests.len() + self.skipped.len()
+	}
+
+	/// Returns the number of test cases skipped by a `skip` directive or by
+	/// the filter, matching the test summary.
+	fn skipped_count(&self) -> usize {
+		let skipped = self.tests.iter().filter(|x| x.skipped.is_some()).count();
+		skipped + self.skipped.len()
+	}
+
+	/// Returns the skip message for the test cases not matching the filter.
+	fn filter_message(&self) -> String {
+		format!(
+			"not matching filter `{}`",
+			self.filter.as_deref().unwrap_or_default()
+		)
+	}
+
+	fn duration(&self) -> Duration {
+		self.tests.iter().map(|x| x.duration).sum()
+	}
+}
+
+/// Writes the reports for the run if [`TESTDATA_REPORT_DIR_VAR`] is set.
+pub(super) fn write_reports_from_env(result: &TestDataResult) {
+	if let Some(dir) = std::env::var_os(TESTDATA_REPORT_DIR_VAR) {
+		if !dir.is_empty() {
+			result.write_reports(dir);
+		}
+	}
+}
+
+/// Returns the JSON object for a test case in the report.
+fn json_case(
+	name: &str,
+	status: TestDataStatus,
+	duration: Duration,
+	message: Option<&str>,
+	diff: Option<&str>,
+) -> String {
+	let mut output = String::new();
+	output.push_str("    {\n");
+	output.push_str(&format!("      \"name\": {},\n", json_string(name)));
+	output.push_str(&format!(
+		"      \"status\": {},\n",
+		json_string(status.as_str())
+	));
+	output.push_str(&format!("      \"duration\": {},\n", seconds(duration)));
+	output.push_str(&format!("      \"message\": {},\n", json_option(message)));
+	output.push_str(&format!("      \"diff\": {}\n", json_option(diff)));
+	output.push_str("    }");
+	output
+}
+
+fn is_error(item: &TestDataResultItem) -> bool {
+	!item.success && item.panic.is_some()
+}
+
+/// Returns the report file name for the run, without extension.
+pub(super) fn report_file_name(name: &str) -> String {
+	let name = name
+		.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+				c
+			} else {
+				'_'
+			}
+		})
+		.collect::<String>();
+	let name = name.trim_matches(|c| c == '_' || c == '.');
+	if name.is_empty() {
+		"testdata".into()
+	} else {
+		name.into()
+	}
+}
+
+fn seconds(duration: Duration) -> String {
+	format!("{:.6}", duration.as_secs_f64())
+}
+
+fn json_option(value: Option<&str>) -> String {
+	match value {
+		Some(value) => json_string(value),
+		None => "null".into(),
+	}
+}
+
+fn json_string(value: &str) -> String {
+	let mut output = String::with_capacity(value.len() + 2);
+	output.push('"');
+	for c in value.chars() {
+		match c {
+			'"' => output.push_str("\\\""),
+			'\\' => output.push_str("\\\\"),
+			'\n' => output.push_str("\\n"),
+			'\r' => output.push_str("\\r"),
+			'\t' => output.push_str("\\t"),
+			c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+			c => output.push(c),
+		}
+	}
+	output.push('"');
+	output
+}
+
+fn xml_escape(value: &str) -> String {
+	let mut output = String::with_capacity(value.len());
+	for c in value.chars() {
+		match c {
+			'&' => output.push_str("&amp;"),
+			'<' => output.push_str("&lt;"),
+			'>' => output.push_str("&gt;"),
+			'"' => output.push_str("&quot;"),
+			'\'' => output.push_str("&apos;"),
+			// other control characters are not allowed in XML 1.0
+			'\n' | '\r' | '\t' => output.push(c),
+			c if (c as u32) < 0x20 => output.push(char::REPLACEMENT_CHARACTER),
+			c => output.push(c),
+		}
+	}
+	output
+}
+
+#[cfg(test)]
+mod test_report {
+	use super::{json_string, report_file_name, xml_escape};
+
+	#[test]
+	fn escapes_json_strings() {
+		let out = json_string("a \"b\" \\ c\n\t\u{1}");
+		assert_eq!(out, r#""a \"b\" \\ c\n\t\u0001""#);
+	}
+
+	#[test]
+	fn escapes_xml_text() {
+		let out = xml_escape("<a href=\"x\">&'</a>\u{1b}");
+		assert_eq!(
+			out,
+			"&lt;a href=&quot;x&quot;&gt;&amp;&apos;&lt;/a&gt;\u{fffd}"
+		);
+	}
+
+	#[test]
+	fn report_file_name_from_run_name() {
+		assert_eq!(report_file_name("tests/testdata"), "tests_testdata");
+		assert_eq!(report_file_name("/tmp/cases/a.input"), "tmp_cases_a.input");
+		assert_eq!(report_file_name(""), "testdata");
+	}
+}
@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+/// Groups the result of a [`TestData`](super::TestData) run.
+///
+/// See [`TestData::run_to_result`](super::TestData::run_to_result) and
+/// [`TestDataResult::write_reports`].
+#[derive(Debug)]
+pub struct TestDataResult {
+	/// Name of the run. This is the test directory path, followed by the
+	/// test case name when running a single case.
+	pub name: String,
+
+	/// Results for each test case in the run, in order.
+	pub tests: Vec<TestDataResultItem>,
+
+	/// Names of the test cases skipped because they did not match the
+	/// filter pattern.
+	pub skipped: Vec<String>,
+
+	/// The filter pattern used in the run, if any.
+	pub filter: Option<String>,
+
+	/// Orphaned and stale files found in the test directory, when not in
+	/// strict mode. See [`TestData::strict`](super::TestData::strict).
+	pub warnings: Vec<String>,
+}
+
+/// Contains information about a single test case, that is, the result of
+/// running the test callback for a single `.input` file.
+#[derive(Debug)]
+pub struct TestDataResultItem {
+	/// Returns if this test case was successful.
+	pub success: bool,
+
+	/// Returns if the valid file was updated with the test output because
+	/// of the bless mode. Blessed tests are also successful.
+	pub blessed: bool,
+
+	/// The test case name. This is the input file name, without path.
+	pub name: String,
+
+	/// Name for the valid file containing the expected test output. For
+	/// a fallible test that returned an error, this is the error file.
+	pub valid_file: String,
+
+	/// Expected test output from the valid file. This will be `None` if the
+	/// test failed because the valid file was not found.
+	pub expect: Option<Vec<String>>,
+
+	/// Actual output form the test callback.
+	pub actual: Vec<String>,
+
+	/// Panic message if the test callback panicked. In this case the test
+	/// failed and there is no actual output.
+	pub panic: Option<String>,
+
+	/// Describes a failure other than an output mismatch or a panic. For
+	/// example, a fallible test that returned an error when only the valid
+	/// file exists, or an invalid directive in the input file.
+	pub unexpected: Option<String>,
+
+	/// Set if the test case was skipped by a `skip` directive, with the
+	/// reason given, if any. Skipped tests are successful.
+	pub skipped: Option<String>,
+
+	/// Returns if the test case failed as expected because of an `xfail`
+	/// directive. Expected failures are successful.
+	pub expected_failure: bool,
+
+	/// Mismatched files for a directory test case, or mismatched outputs
+	/// for a binary test case.
+	pub files: Vec<TestDataFileResult>,
+
+	/// Time spent running the test case.
+	pub duration: Duration,
+}
+
+/// Mismatched file in the output of a directory test case.
+#[derive(Debug)]
+pub struct TestDataFileResult {
+	/// File path relative to the output directory, using `/` as separator.
+	pub path: String,
+
+	/// Expected file contents. This is `None` if the file is not expected,
+	/// or if the file with the expected contents (see `expect_file`) was not
+	/// found.
+	pub expect: Option<Vec<String>>,
+
+	/// Actual file contents. This is `None` if the file is missing.
+	pub actual: Option<Vec<String>>,
+
+	/// Name of the file with the expected contents, if it is not part of an
+	/// expected directory tree. For example, the `.stderr` file of a
+	/// [`TestData::run_bin`](super::TestData::run_bin) case.
+	pub expect_file: Option<String>,
+}
+
+impl TestDataResultItem {
+	/// Returns a failed result without any output.
+	pub(super) fn new(name: String, valid_file: String) -> Self {
+		TestDataResultItem {
+			success: false,
+			blessed: false,
+			name,
+			valid_file,
+			expect: None,
+			actual: Vec::new(),
+			panic: None,
+			unexpected: None,
+			skipped: None,
+			expected_failure: false,
+			files: Vec::new(),
+			duration: Duration::ZERO,
+		}
+	}
+
+	/// Returns the status of the test case.
+	pub fn status(&self) -> TestDataStatus {
+		if self.blessed {
+			TestDataStatus::Blessed
+		} else if self.skipped.is_some() {
+			TestDataStatus::Skipped
+		} else if self.expected_failure {
+			TestDataStatus::ExpectedFailure
+		} else if self.success {
+			TestDataStatus::Passed
+		} else {
+			TestDataStatus::Failed
+		}
+	}
+
+	/// Returns a short description of the failure, or `None` if the test case
+	/// did not fail.
+	pub fn message(&self) -> Option<String> {
+		if self.success {
+			None
+		} else if let Some(panic) = &self.panic {
+			Some(format!("panicked: {}", panic))
+		} else if let Some(unexpected) = &self.unexpected {
+			Some(unexpected.clone())
+		} else if self.expect.is_some() || !self.files.is_empty() {
+			Some(format!("output did not match `{}`", self.valid_file))
+		} else {
+			Some(format!("`{}` not found", self.valid_file))
+		}
+	}
+
+	/// Returns the unified diff between the actual and the expected output,
+	/// or `None` if the test case did not fail with an output mismatch.
+	///
+	/// For a directory test case, this contains a diff for each mismatched
+	/// file, and a line for each missing or unexpected file.
+	pub fn diff(&self) -> Option<String> {
+		self.diff_with_color(false)
+	}
+
+	pub(super) fn diff_with_color(&self, color: bool) -> Option<String> {
+		if self.success || self.panic.is_some() {
+			return None;
+		}
+
+		// other failures only have a diff if the output did not match too,
+		// e.g. for an invalid placeholder in the expected output
+		let expect = self
+			.expect
+			.as_ref()
+			.filter(|x| self.unexpected.is_none() || self.actual != **x);
+		if let Some(expected) = expect {
+			return Some(diff_text(
+				&self.actual,
+				expected,
+				&self.name,
+				&self.valid_file,
+				color,
+			));
+		}
+
+		if self.files.is_empty() {
+			return None;
+		}
+
+		let mut output = Vec::new();
+		for file in self.files.iter() {
+			let (actual_label, expected_label) = match &file.expect_file {
+				Some(expect_file) => (
+					format!("{} ({})", self.name, file.path),
+					expect_file.clone(),
+				),
+				None => (
+					format!("{}/{}", self.name, file.path),
+					format!("{}/{}", self.valid_file, file.path),
+				),
+			};
+			output.push(match (&file.actual, &file.expect) {
+				(Some(actual), Some(expected)) => {
+					diff_text(actual, expected, &actual_label, &expected_label, color)
+				}
+				(Some(_), None) => match &file.expect_file {
+					Some(expect_file) => format!(
+						".. `{}` not found, created `{}.new` with the current output",
+						expect_file, expect_file
+					),
+					None => format!(".. unexpected file `{}`", file.path),
+				},
+				(None, _) => format!(".. missing file `{}`", file.path),
+			});
+		}
+		Some(output.join("\n\n"))
+	}
+}
+
+/// Status of a single test case in a [`TestDataResult`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TestDataStatus {
+	/// The test output matched the expected output.
+	Passed,
+	/// The test case failed.
+	Failed,
+	/// The test case was skipped by a `skip` directive.
+	Skipped,
+	/// The expected output was updated with the test output.
+	Blessed,
+	/// The test case failed as expected because of an `xfail` directive.
+	ExpectedFailure,
+}
+
+impl TestDataStatus {
+	/// Returns the status name used in reports (e.g. `passed`).
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			TestDataStatus::Passed => "passed",
+			TestDataStatus::Failed => "failed",
+			TestDataStatus::Skipped => "skipped",
+			TestDataStatus::Blessed => "blessed",
+			TestDataStatus::ExpectedFailure => "expected_failure",
+		}
+	}
+}
+
+/// Returns the unified diff text for the lines.
+fn diff_text(
+	actual: &[String],
+	expected: &[String],
+	actual_label: &str,
+	expected_label: &str,
+	color: bool,
+) -> String {
+	let diff = crate::diff::lines(actual, expected);
+	let diff = diff.unified().labels(actual_label, expected_label);
+	// without colors, the inline changes are shown with `[-old-]{+new+}` markers
+	let diff = diff.color(color).inline(crate::diff::InlineMode::Words);
+	diff.to_string()
+}
+
+impl TestDataResult {
+	/// Returns `true` if and only if all tests succeeded.
+	pub fn success(&self) -> bool {
+		for it in self.tests.iter() {
+			if !it.success {
+				return false;
+			}
+		}
+		true
+	}
+}
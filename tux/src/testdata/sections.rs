@@ -0,0 +1,335 @@
+use std::time::Instant;
+
+use super::{
+	catch_panic, collect_test_inputs_with_name, expect_fail_result, has_placeholders,
+	matches_placeholders, output_result, parse_test_case, suppress_panic_output, MissingValid,
+	TestData, TestDataResult, TestDataResultItem, Watchdog,
+};
+
+const TEST_SECTIONS_FILE_EXTENSION: &str = "test";
+const INPUT_SECTION: &str = "-- input --";
+const OUTPUT_SECTION: &str = "-- output --";
+
+impl TestData {
+	/// Runs the tests from `.test` files, where each file contains one or
+	/// more test cases with an input and an output section:
+	///
+	/// ```text
+	/// Any text before the first section is a comment.
+	/// -- input --
+	/// input lines
+	/// -- output --
+	/// expected output lines
+	/// -- input --
+	/// input for the second case
+	/// -- output --
+	/// expected output for the second case
+	/// ```
+	///
+	/// The test callback and the comparison are the same as [`TestData::run`],
+	/// with the input section lines passed to the callback and the result
+	/// compared with the output section. Input sections can also start with
+	/// directives (see [`TestCase`](super::TestCase)).
+	///
+	/// Files with a single case use the file path as test name. Otherwise,
+	/// the test name includes the case number (e.g. `parser/if.test#2`).
+	///
+	/// A case without an output section fails, showing the actual output. In
+	/// bless mode, or with [`MissingValid::Create`], the output sections are
+	/// rewritten in place with the actual output.
+	///
+	/// See also [`testdata_sections`](super::testdata_sections).
+	pub fn run_sections<F>(self, callback: F)
+	where
+		F: FnMut(Vec<String>) -> Vec<String>,
+	{
+		let result = self.run_sections_to_result(callback);
+		output_result(&result);
+	}
+
+	pub(super) fn run_sections_to_result<F>(&self, mut test_callback: F) -> TestDataResult
+	where
+		F: FnMut(Vec<String>) -> Vec<String>,
+	{
+		let mut test_results = Vec::new();
+		let mut skipped = Vec::new();
+		let test_files_with_name =
+			collect_test_inputs_with_name(&self.path, TEST_SECTIONS_FILE_EXTENSION);
+
+		let watchdog = Watchdog::new();
+		let _hook = suppress_panic_output();
+		for (file_path, file_name) in test_files_with_name.into_iter() {
+			let text = std::fs::read_to_string(&file_path).expect("reading test file");
+			let file_label = file_path.file_name().unwrap().to_string_lossy().to_string();
+
+			let mut file = match SectionFile::parse(&text) {
+				Ok(file) => file,
+				Err(err) => {
+					if self.matches_filter(&file_name) {
+						let mut item = TestDataResultItem::new(file_name, file_label);
+						item.unexpected = Some(err);
+						test_results.push(item);
+					} else {
+						skipped.push(file_name);
+					}
+					continue;
+				}
+			};
+
+			let mut updated = false;
+			let case_count = file.cases.len();
+			for (index, section) in file.cases.iter_mut().enumerate() {
+				let test_name = if case_count == 1 {
+					file_name.clone()
+				} else {
+					format!("{}#{}", file_name, index + 1)
+				};
+				if !self.matches_filter(&test_name) {
+					skipped.push(test_name);
+					continue;
+				}
+
+				let start = Instant::now();
+				let mut item = self.run_section(
+					test_name,
+					&file_label,
+					section,
+					&watchdog,
+					&mut test_callback,
+				);
+				item.duration = start.elapsed();
+				updated = updated || item.blessed;
+				test_results.push(item);
+			}
+
+			if updated {
+				std::fs::write(&file_path, file.to_text()).expect("writing blessed test file");
+			}
+		}
+
+		self.to_result(test_results, skipped)
+	}
+
+	/// Runs a single test case, updating the output section if blessed.
+	fn run_section<F>(
+		&self,
+		test_name: String,
+		file_label: &str,
+		section: &mut SectionCase,
+		watchdog: &Watchdog,
+		test_callback: F,
+	) -> TestDataResultItem
+	where
+		F: FnOnce(Vec<String>) -> Vec<String>,
+	{
+		let mut item = TestDataResultItem::new(test_name, file_label.to_string());
+
+		let (case, input) = match parse_test_case(&item.name, &section.input) {
+			Ok(result) => result,
+			Err(err) => {
+				item.unexpected = Some(err);
+				return item;
+			}
+		};
+
+		if case.skip() {
+			item.success = true;
+			item.skipped = Some(case.skip_reason().unwrap_or_default().to_string());
+			return item;
+		}
+
+		let input = self.read_lines(input.to_string());
+		let watch = watchdog.watch(&item.name, case.timeout().or(self.timeout));
+		let output = catch_panic(|| test_callback(input));
+		if let Some(timeout) = watch.timed_out() {
+			item.unexpected = Some(format!("timed out after {:?}", timeout));
+			return item;
+		}
+		let output = match output {
+			Ok(output) => output,
+			Err(panic) => {
+				item.panic = Some(panic);
+				return expect_fail_result(&case, item);
+			}
+		};
+
+		// the expected output of a failing case is not blessed
+		let bless = self.bless && !case.expect_fail();
+		// the output is redacted as a whole, so rules can match across lines
+		let output_text = self.redact_text(output.join("\n"));
+		let output = if output_text.is_empty() {
+			Vec::new()
+		} else {
+			output_text.split('\n').map(String::from).collect()
+		};
+		match &section.output {
+			Some(expected) => {
+				let expected = self.read_lines(expected.clone());
+				item.success = output_text == expected.join("\n");
+				if !item.success && has_placeholders(&expected) {
+					match matches_placeholders(&output, &expected) {
+						Ok(matched) => item.success = matched,
+						Err(err) => {
+							item.unexpected = Some(format!(
+								"has an invalid placeholder in the `{}` section at {}",
+								OUTPUT_SECTION, err
+							))
+						}
+					}
+				}
+				item.expect = Some(expected);
+			}
+			None => {
+				let create = self.missing_valid == MissingValid::Create && !case.expect_fail();
+				if !bless && !create {
+					item.unexpected = Some(format!("has no `{}` section", OUTPUT_SECTION));
+				}
+				item.blessed = bless || create;
+			}
+		}
+
+		item.blessed = item.blessed || (bless && !item.success);
+		if item.blessed {
+			// blessing replaces the output section, including invalid placeholders
+			item.success = true;
+			item.unexpected = None;
+			section.output = Some(if output_text.is_empty() {
+				output_text
+			} else {
+				format!("{}\n", output_text)
+			});
+		}
+
+		item.actual = output;
+		expect_fail_result(&case, item)
+	}
+}
+
+/// Parsed `.test` file. See [`TestData::run_sections`].
+#[derive(Debug, PartialEq, Eq)]
+struct SectionFile {
+	/// Text before the first section.
+	preamble: String,
+	cases: Vec<SectionCase>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct SectionCase {
+	input: String,
+	output: Option<String>,
+}
+
+impl SectionFile {
+	fn parse(text: &str) -> Result<SectionFile, String> {
+		let mut file = SectionFile {
+			preamble: String::new(),
+			cases: Vec::new(),
+		};
+
+		for (line_index, line) in text.split_inclusive('\n').enumerate() {
+			let marker = line.trim_end();
+			if marker == INPUT_SECTION {
+				file.cases.push(SectionCase {
+					input: String::new(),
+					output: None,
+				});
+			} else if marker == OUTPUT_SECTION {
+				match file.cases.last_mut() {
+					Some(case) if case.output.is_none() => case.output = Some(String::new()),
+					_ => {
+						return Err(format!(
+							"unexpected `{}` section at line {}",
+							OUTPUT_SECTION,
+							line_index + 1
+						))
+					}
+				}
+			} else {
+				let target = match file.cases.last_mut() {
+					Some(SectionCase {
+						output: Some(output),
+						..
+					}) => output,
+					Some(case) => &mut case.input,
+					None => &mut file.preamble,
+				};
+				target.push_str(line);
+			}
+		}
+
+		if file.cases.is_empty() {
+			return Err(format!("no `{}` section found", INPUT_SECTION));
+		}
+
+		Ok(file)
+	}
+
+	fn to_text(&self) -> String {
+		let mut text = self.preamble.clone();
+		for case in self.cases.iter() {
+			push_section(&mut text, INPUT_SECTION, &case.input);
+			if let Some(output) = &case.output {
+				push_section(&mut text, OUTPUT_SECTION, output);
+			}
+		}
+		text
+	}
+}
+
+fn push_section(text: &mut String, marker: &str, content: &str) {
+	// a section that is not the last one always ends with a line break
+	if !text.is_empty() && !text.ends_with('\n') {
+		text.push('\n');
+	}
+	text.push_str(marker);
+	text.push('\n');
+	text.push_str(content);
+}
+
+#[cfg(test)]
+mod test_sections {
+	use super::{SectionCase, SectionFile};
+
+	#[test]
+	fn parses_single_case() {
+		let file = SectionFile::parse("-- input --\na\nb\n-- output --\nc\n").unwrap();
+		assert_eq!(file.preamble, "");
+		assert_eq!(
+			file.cases,
+			[SectionCase {
+				input: "a\nb\n".into(),
+				output: Some("c\n".into()),
+			}]
+		);
+	}
+
+	#[test]
+	fn parses_multiple_cases_and_preamble() {
+		let text = "comment\n-- input --\na\n-- output --\nb\n-- input --\nc\n";
+		let file = SectionFile::parse(text).unwrap();
+		assert_eq!(file.preamble, "comment\n");
+		assert_eq!(file.cases.len(), 2);
+		assert_eq!(file.cases[1].input, "c\n");
+		assert_eq!(file.cases[1].output, None);
+		assert_eq!(file.to_text(), text);
+	}
+
+	#[test]
+	fn to_text_adds_line_break_before_section() {
+		let mut file = SectionFile::parse("-- input --\na").unwrap();
+		file.cases[0].output = Some("b\n".into());
+		assert_eq!(file.to_text(), "-- input --\na\n-- output --\nb\n");
+	}
+
+	#[test]
+	fn fails_for_invalid_sections() {
+		let err = SectionFile::parse("a\n-- output --\n").unwrap_err();
+		assert_eq!(err, "unexpected `-- output --` section at line 2");
+
+		let err = SectionFile::parse("-- input --\n-- output --\n-- output --\n").unwrap_err();
+		assert_eq!(err, "unexpected `-- output --` section at line 3");
+
+		let err = SectionFile::parse("some text\n").unwrap_err();
+		assert_eq!(err, "no `-- input --` section found");
+	}
+}
@@ -0,0 +1,60 @@
+use super::{collect_files_with_name, TestData, TestDataResult, TestDataResultItem};
+
+/// Suffix for the files generated from the test output when the expected
+/// output is not found.
+const NEW_FILE_SUFFIX: &str = ".new";
+
+impl TestData {
+	/// Checks the test directory for orphaned and stale files, that is:
+	///
+	/// - files with one of the expected extensions (or a `.new` file for one)
+	///   without a matching input file;
+	/// - `.new` files for an expected file that exists, which are leftovers
+	///   from a previous run.
+	///
+	/// The files are reported as warnings in the result or, in strict mode,
+	/// as failed test cases.
+	///
+	/// This is not done when running a single test case, since the check
+	/// applies to the whole test directory.
+	pub(super) fn check_stale_files(&self, result: &mut TestDataResult, extensions: &[&str]) {
+		if self.case.is_some() {
+			return;
+		}
+
+		let input_suffix = format!(".{}", self.input_extension);
+		for (_, name) in collect_files_with_name(&self.path) {
+			if name.ends_with(&input_suffix) {
+				continue;
+			}
+
+			let is_new = name.ends_with(NEW_FILE_SUFFIX);
+			let expected_name = name.strip_suffix(NEW_FILE_SUFFIX).unwrap_or(&name);
+			let Some(base_name) = extensions.iter().find_map(|extension| {
+				let suffix = format!(".{}", extension);
+				expected_name
+					.strip_suffix(&suffix)
+					.filter(|x| !x.is_empty() && !x.ends_with('/'))
+			}) else {
+				continue;
+			};
+
+			let input_name = format!("{}{}", base_name, input_suffix);
+			let message = if !self.path.join(&input_name).is_file() {
+				format!("is orphaned, since `{}` does not exist", input_name)
+			} else if is_new && self.path.join(expected_name).is_file() {
+				format!("is stale, since `{}` exists", expected_name)
+			} else {
+				continue;
+			};
+
+			if self.strict {
+				let mut item = TestDataResultItem::new(name.clone(), name);
+				item.unexpected = Some(message);
+				result.tests.push(item);
+			} else {
+				result.warnings.push(format!("`{}` {}", name, message));
+			}
+		}
+	}
+}
@@ -0,0 +1,152 @@
+use std::{
+	io::Write,
+	sync::{Arc, Condvar, Mutex},
+	thread::JoinHandle,
+	time::{Duration, Instant},
+};
+
+/// Supervises the running test cases from a separate thread, reporting the
+/// test cases that are still running past their timeout.
+///
+/// A running callback cannot be interrupted, so a test case that times out
+/// fails once it returns. If it never does, the report shows which test case
+/// is stuck. See [`TestData::run_isolated`](super::TestData::run_isolated)
+/// for running each test case on its own thread, which allows continuing
+/// with the remaining test cases.
+pub(super) struct Watchdog {
+	state: Arc<(Mutex<WatchdogState>, Condvar)>,
+	thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+	next_id: usize,
+	running: Vec<WatchedCase>,
+	done: bool,
+}
+
+struct WatchedCase {
+	id: usize,
+	name: String,
+	timeout: Duration,
+	deadline: Instant,
+	reported: bool,
+}
+
+impl Watchdog {
+	pub fn new() -> Self {
+		let state = Arc::new((Mutex::new(WatchdogState::default()), Condvar::new()));
+		let thread = {
+			let state = state.clone();
+			std::thread::spawn(move || watch_cases(&state))
+		};
+		Watchdog {
+			state,
+			thread: Some(thread),
+		}
+	}
+
+	/// Starts watching a test case until the returned guard is dropped. A
+	/// test case without a timeout is not watched.
+	pub fn watch(&self, name: &str, timeout: Option<Duration>) -> WatchGuard<'_> {
+		let start = Instant::now();
+		let Some(timeout) = timeout else {
+			return WatchGuard {
+				watchdog: self,
+				id: None,
+				timeout: None,
+				start,
+			};
+		};
+
+		let (lock, condvar) = &*self.state;
+		let mut state = lock.lock().unwrap();
+		let id = state.next_id;
+		state.next_id += 1;
+		state.running.push(WatchedCase {
+			id,
+			name: name.to_string(),
+			timeout,
+			deadline: start + timeout,
+			reported: false,
+		});
+		condvar.notify_one();
+		WatchGuard {
+			watchdog: self,
+			id: Some(id),
+			timeout: Some(timeout),
+			start,
+		}
+	}
+}
+
+impl Drop for Watchdog {
+	fn drop(&mut self) {
+		let (lock, condvar) = &*self.state;
+		lock.lock().unwrap().done = true;
+		condvar.notify_one();
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+/// Stops watching a test case when dropped. See [`Watchdog::watch`].
+pub(super) struct WatchGuard<'a> {
+	watchdog: &'a Watchdog,
+	id: Option<usize>,
+	timeout: Option<Duration>,
+	start: Instant,
+}
+
+impl<'a> WatchGuard<'a> {
+	/// Returns the timeout if the test case ran past it.
+	pub fn timed_out(&self) -> Option<Duration> {
+		self.timeout.filter(|x| self.start.elapsed() > *x)
+	}
+}
+
+impl<'a> Drop for WatchGuard<'a> {
+	fn drop(&mut self) {
+		if let Some(id) = self.id {
+			let (lock, _) = &*self.watchdog.state;
+			lock.lock().unwrap().running.retain(|x| x.id != id);
+		}
+	}
+}
+
+fn watch_cases(state: &(Mutex<WatchdogState>, Condvar)) {
+	let (lock, condvar) = state;
+	let mut state = lock.lock().unwrap();
+	loop {
+		if state.done {
+			return;
+		}
+
+		let now = Instant::now();
+		for case in state.running.iter_mut() {
+			if case.deadline <= now && !case.reported {
+				// the output capture of the test harness is bypassed, since it
+				// is only shown once the test finishes
+				let _ = writeln!(
+					std::io::stderr(),
+					"\n=> `{}` is still running after the {:?} timeout",
+					case.name,
+					case.timeout
+				);
+				case.reported = true;
+			}
+		}
+
+		let next_deadline = state
+			.running
+			.iter()
+			.filter(|x| !x.reported)
+			.map(|x| x.deadline)
+			.min();
+		state = match next_deadline {
+			Some(deadline) => condvar.wait_timeout(state, deadline - now).unwrap().0,
+			None => condvar.wait(state).unwrap(),
+		};
+	}
+}
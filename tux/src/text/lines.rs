@@ -14,6 +14,18 @@ pub fn lines<S: AsRef<str>>(input: S) -> Vec<String> {
 	trim_lines(output).collect()
 }
 
+/// Splits the input string into lines without any whitespace cleanup.
+///
+/// This uses the same line separators as [`lines`], and a line break at the
+/// end of the input does not generate an empty line.
+pub fn split_lines<S: AsRef<str>>(input: S) -> Vec<String> {
+	let output = LinesIterator {
+		input_text: input.as_ref(),
+		cursor_pos: 0,
+	};
+	output.map(|x| x.to_string()).collect()
+}
+
 /// Removes extra space from an input string sequence received as an iterator.
 ///
 /// This return an iterator wrapping the original input that will:
@@ -21,7 +33,7 @@ pub fn lines<S: AsRef<str>>(input: S) -> Vec<String> {
 /// - Remove leading and trailing blank lines. A blank line is either an
 ///   empty string or a string containing only spaces.
 /// - Trim the end of all lines.
-pub fn trim_lines<'a, I>(input: I) -> impl Iterator<Item = String>
+pub fn trim_lines<I>(input: I) -> impl Iterator<Item = String>
 where
 	I: IntoIterator,
 	I::Item: AsRef<str>,
@@ -29,7 +41,7 @@ where
 	let output = input
 		.into_iter()
 		.map(|x| x.as_ref().trim_end().to_string())
-		.skip_while(|x| x.len() == 0);
+		.skip_while(|x| x.is_empty());
 
 	// note that this only handles empty strings, so we need to make sure
 	// lines are already trimmed before they go through this iterator
@@ -48,17 +60,17 @@ impl<'a> Iterator for LinesIterator<'a> {
 
 	fn next(&mut self) -> Option<Self::Item> {
 		let remaining_input = &self.input_text[self.cursor_pos..];
-		if remaining_input.len() == 0 {
+		if remaining_input.is_empty() {
 			None
-		} else if let Some(next_line_break_offset) = remaining_input.find(&['\n', '\r']) {
+		} else if let Some(next_line_break_offset) = remaining_input.find(['\n', '\r']) {
 			let remaining_as_bytes = remaining_input.as_bytes();
 			let current_line = &remaining_input[..next_line_break_offset];
 
 			let last_character_index = remaining_as_bytes.len() - 1;
-			let is_carriage_return = remaining_as_bytes[next_line_break_offset] == '\r' as u8;
+			let is_carriage_return = remaining_as_bytes[next_line_break_offset] == b'\r';
 			let is_crlf_sequence = is_carriage_return
 				&& next_line_break_offset < last_character_index
-				&& remaining_as_bytes[next_line_break_offset + 1] == '\n' as u8;
+				&& remaining_as_bytes[next_line_break_offset + 1] == b'\n';
 
 			let next_cursor_offset = if is_crlf_sequence {
 				next_line_break_offset + 2
@@ -114,7 +126,7 @@ impl<T: Iterator<Item = String>> Iterator for TrimEndIterator<T> {
 			}
 
 			if let Some(next) = self.inner.next() {
-				if next.len() > 0 {
+				if !next.is_empty() {
 					// we can't return this directly because there may have
 					// been a run of empty strings before it that we need to
 					// return
@@ -131,7 +143,7 @@ impl<T: Iterator<Item = String>> Iterator for TrimEndIterator<T> {
 
 #[cfg(test)]
 mod test_lines {
-	use super::lines;
+	use super::{lines, split_lines};
 
 	#[test]
 	fn returns_single_line_for_no_line_break() {
@@ -148,7 +160,7 @@ mod test_lines {
 	#[test]
 	fn returns_empty_for_empty_string() {
 		let out = lines("");
-		assert!(out.len() == 0);
+		assert!(out.is_empty());
 	}
 
 	#[test]
@@ -174,6 +186,12 @@ mod test_lines {
 		let out = lines("\n 1\n  2\n   3");
 		assert_eq!(out, vec![" 1", "  2", "   3"]);
 	}
+
+	#[test]
+	fn split_lines_preserves_whitespace() {
+		let out = split_lines("\n  a  \r\nb\r\n\n");
+		assert_eq!(out, vec!["", "  a  ", "b", ""]);
+	}
 }
 
 #[cfg(test)]
@@ -182,28 +200,28 @@ mod test_trim_lines {
 
 	#[test]
 	fn returns_non_empty_lines() {
-		let input = vec!["1", "2", "3"];
+		let input = ["1", "2", "3"];
 		let out = trim_lines(input.iter()).collect::<Vec<_>>();
 		assert_eq!(out, vec!["1", "2", "3"]);
 	}
 
 	#[test]
 	fn skips_leading_blank_lines() {
-		let input = vec!["", "  ", "\t", "first non-empty", "second"];
+		let input = ["", "  ", "\t", "first non-empty", "second"];
 		let out = trim_lines(input.iter()).collect::<Vec<_>>();
 		assert_eq!(out, vec!["first non-empty", "second"]);
 	}
 
 	#[test]
 	fn removes_trailing_space_from_lines() {
-		let input = vec!["l1  ", "l2\t", "l3\t ", "l4  "];
+		let input = ["l1  ", "l2\t", "l3\t ", "l4  "];
 		let out = trim_lines(input.iter()).collect::<Vec<_>>();
 		assert_eq!(out, vec!["l1", "l2", "l3", "l4"]);
 	}
 
 	#[test]
 	fn removes_trailing_blank_lines() {
-		let input = vec!["first", "last non-empty", "", "  ", "\t"];
+		let input = ["first", "last non-empty", "", "  ", "\t"];
 		let out = trim_lines(input.iter()).collect::<Vec<_>>();
 		assert_eq!(out, vec!["first", "last non-empty"]);
 	}
@@ -217,7 +235,7 @@ mod test_trim_lines {
 
 	#[test]
 	fn does_not_trim_lead_indentation() {
-		let input = vec![" 1", "  2", "   3"];
+		let input = [" 1", "  2", "   3"];
 		let out = trim_lines(input.iter()).collect::<Vec<_>>();
 		assert_eq!(out, vec![" 1", "  2", "   3"]);
 	}
@@ -37,10 +37,10 @@
 /// ].join("\n");
 /// assert_eq!(output, expected);
 /// ```
-///
+#[allow(clippy::tabs_in_doc_comments)]
 pub fn trim<S: AsRef<str>>(input: S) -> String {
 	let lines = super::lines(input);
-	if lines.len() == 0 {
+	if lines.is_empty() {
 		return String::new();
 	}
 	let first = lines[0].as_str();
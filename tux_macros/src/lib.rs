@@ -0,0 +1,249 @@
+//! Procedural macros for the `tux` library.
+//!
+//! This crate is not meant to be used directly. The macros are re-exported
+//! by `tux` with their respective features.
+
+use std::{
+	collections::{HashSet, VecDeque},
+	path::{Path, PathBuf},
+};
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+// This must match the default input extension for `tux::testdata`.
+const TEST_INPUT_FILE_EXTENSION: &str = "input";
+
+/// Generates one `#[test]` function for each `.input` file in a directory,
+/// using the annotated function as the test callback.
+///
+/// This is the compile time version of the `testdata` function. Each case
+/// runs as a separate test, so failures are reported individually by the
+/// test harness and a single case can be run with `cargo test NAME`.
+///
+/// The directory is relative to the crate root and is scanned recursively
+/// for `.input` files when the crate is compiled. The generated tests are
+/// placed in a module with the same name as the function, with the test
+/// name derived from the input path (e.g. `parser/if-else.input` generates
+/// `parser::parser_if_else` for a `parser` function).
+///
+/// ```ignore
+/// #[tux::testdata("tests/testdata/parser")]
+/// fn parser(input: Vec<String>) -> Vec<String> {
+///     parse(input)
+/// }
+/// ```
+///
+/// Each test case is compared to its `.valid` file in the same way as the
+/// `testdata` function, including the failure diff and the bless mode.
+///
+/// Changes to existing input files cause the tests to be rebuilt, but new
+/// input files are only picked up once the test source is recompiled (e.g.
+/// by touching the test file or with `cargo clean`).
+#[proc_macro_attribute]
+pub fn testdata(attr: TokenStream, item: TokenStream) -> TokenStream {
+	match expand_testdata(attr, &item) {
+		Ok(output) => output,
+		Err(error) => {
+			let error = format!("compile_error!({:?});", error);
+			let mut output = item;
+			output.extend(error.parse::<TokenStream>().unwrap());
+			output
+		}
+	}
+}
+
+fn expand_testdata(attr: TokenStream, item: &TokenStream) -> Result<TokenStream, String> {
+	let path = parse_path_argument(attr)?;
+	let callback = parse_function_name(item)?;
+
+	let manifest_dir =
+		std::env::var("CARGO_MANIFEST_DIR").map_err(|_| "CARGO_MANIFEST_DIR is not set")?;
+	let test_dir = Path::new(&manifest_dir).join(&path);
+	if !test_dir.is_dir() {
+		return Err(format!(
+			"testdata directory `{}` not found",
+			test_dir.display()
+		));
+	}
+
+	let mut tests = String::new();
+	let mut test_names = HashSet::new();
+	for (input_path, case_name) in collect_test_inputs_with_name(&test_dir)? {
+		let mut test_name = test_name_for(&case_name);
+		let mut suffix = 1;
+		while !test_names.insert(test_name.clone()) {
+			suffix += 1;
+			test_name = format!("{}_{}", test_name_for(&case_name), suffix);
+		}
+
+		// including the input makes the tests rebuild when it changes
+		tests.push_str(&format!(
+			r#"
+			#[test]
+			fn {test_name}() {{
+				const _: &[u8] = include_bytes!({input_path:?});
+				::tux::testdata_case(
+					concat!(env!("CARGO_MANIFEST_DIR"), "/", {path:?}),
+					{case_name:?},
+					super::{callback},
+				);
+			}}
+			"#,
+			input_path = input_path.to_string_lossy(),
+		));
+	}
+
+	let module = format!("#[cfg(test)] mod {callback} {{ {tests} }}");
+	let module = module
+		.parse::<TokenStream>()
+		.map_err(|err| err.to_string())?;
+
+	let mut output = item.clone();
+	output.extend(module);
+	Ok(output)
+}
+
+/// Parses the directory argument, which must be a single string literal.
+fn parse_path_argument(attr: TokenStream) -> Result<String, String> {
+	const USAGE: &str = "expected a directory path, as in `#[testdata(\"tests/testdata\")]`";
+
+	let mut tokens = attr.into_iter();
+	let literal = match (tokens.next(), tokens.next()) {
+		(Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+		_ => return Err(USAGE.into()),
+	};
+
+	if let Some(raw) = literal.strip_prefix('r') {
+		let hashes = raw.len() - raw.trim_start_matches('#').len();
+		let raw = &raw[hashes..raw.len() - hashes];
+		let text = raw.strip_prefix('"').and_then(|x| x.strip_suffix('"'));
+		text.map(|x| x.to_string()).ok_or_else(|| USAGE.into())
+	} else if let Some(text) = literal.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+		unescape(text)
+	} else {
+		Err(USAGE.into())
+	}
+}
+
+fn unescape(text: &str) -> Result<String, String> {
+	let mut output = String::new();
+	let mut chars = text.chars();
+	while let Some(char) = chars.next() {
+		if char != '\\' {
+			output.push(char);
+			continue;
+		}
+		match chars.next() {
+			Some('\\') => output.push('\\'),
+			Some('"') => output.push('"'),
+			Some('\'') => output.push('\''),
+			Some('n') => output.push('\n'),
+			Some('t') => output.push('\t'),
+			_ => return Err("unsupported escape sequence in directory path".into()),
+		}
+	}
+	Ok(output)
+}
+
+/// Returns the name of the annotated function.
+fn parse_function_name(item: &TokenStream) -> Result<String, String> {
+	let mut tokens = item.clone().into_iter();
+	while let Some(token) = tokens.next() {
+		match token {
+			TokenTree::Ident(ident) if ident.to_string() == "fn" => {
+				if let Some(TokenTree::Ident(name)) = tokens.next() {
+					return Ok(name.to_string());
+				}
+				break;
+			}
+			TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => break,
+			_ => {}
+		}
+	}
+	Err("the `testdata` attribute can only be applied to functions".into())
+}
+
+/// Returns the path and name of all `.input` files in the directory. This
+/// is the same as the test collection used by `tux::testdata`.
+fn collect_test_inputs_with_name(root_path: &Path) -> Result<Vec<(PathBuf, String)>, String> {
+	let mut test_inputs_with_name = Vec::new();
+
+	let input_suffix = format!(".{}", TEST_INPUT_FILE_EXTENSION);
+
+	let mut dirs_to_scan_with_name = VecDeque::new();
+	dirs_to_scan_with_name.push_back((root_path.to_owned(), String::new()));
+
+	while let Some((current_dir, current_name)) = dirs_to_scan_with_name.pop_front() {
+		let entries = std::fs::read_dir(&current_dir).map_err(|err| err.to_string())?;
+		let mut entries = entries
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|err| err.to_string())?;
+		entries.sort_by_key(|x| x.file_name());
+
+		for entry in entries {
+			let entry_path = entry.path();
+			let file_name = entry.file_name().to_string_lossy().to_string();
+			let entry_name = if !current_name.is_empty() {
+				format!("{}/{}", current_name, file_name)
+			} else {
+				file_name.clone()
+			};
+
+			if entry_path.is_dir() {
+				dirs_to_scan_with_name.push_back((entry_path, entry_name));
+			} else if file_name.len() > input_suffix.len() && file_name.ends_with(&input_suffix) {
+				test_inputs_with_name.push((entry_path, entry_name));
+			}
+		}
+	}
+
+	Ok(test_inputs_with_name)
+}
+
+/// Converts a test case name into a valid function name, e.g.
+/// `parser/if-else.input` becomes `parser_if_else`.
+fn test_name_for(case_name: &str) -> String {
+	let name = case_name.strip_suffix(&format!(".{}", TEST_INPUT_FILE_EXTENSION));
+	let name = name.unwrap_or(case_name);
+	let mut name = name
+		.chars()
+		.map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+		.collect::<String>();
+	if name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() {
+		name.insert_str(0, "case_");
+	} else if is_keyword(&name) {
+		name.push('_');
+	}
+	name
+}
+
+fn is_keyword(name: &str) -> bool {
+	const KEYWORDS: &[&str] = &[
+		"_", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+		"crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+		"impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv",
+		"pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try",
+		"type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+	];
+	KEYWORDS.contains(&name) || name == "Self"
+}
+
+#[cfg(test)]
+mod test_macros {
+	use super::{test_name_for, unescape};
+
+	#[test]
+	fn test_name_from_case_name() {
+		assert_eq!(test_name_for("simple.input"), "simple");
+		assert_eq!(test_name_for("parser/if-else.input"), "parser_if_else");
+		assert_eq!(test_name_for("01 basic.input"), "case_01_basic");
+		assert_eq!(test_name_for("while.input"), "while_");
+	}
+
+	#[test]
+	fn unescape_path() {
+		assert_eq!(unescape(r"tests\\data").unwrap(), r"tests\data");
+		assert_eq!(unescape(r#"a\"b"#).unwrap(), "a\"b");
+		assert!(unescape(r"\x41").is_err());
+	}
+}
@@ -0,0 +1,29 @@
+//! This program is used to test the `testdata_bin` tests, which feed the
+//! test input to a binary and compare its outputs.
+
+use std::io::BufRead;
+
+fn main() {
+	let upper = std::env::args().skip(1).any(|x| x == "--upper");
+	let prefix = std::env::var("FILTER_PREFIX").unwrap_or_default();
+
+	let mut failed = false;
+	for line in std::io::stdin().lock().lines() {
+		let line = line.expect("reading input");
+		if let Some(seconds) = line.strip_prefix("sleep:") {
+			let seconds = seconds.trim().parse().expect("parsing sleep seconds");
+			std::thread::sleep(std::time::Duration::from_secs(seconds));
+		} else if let Some(error) = line.strip_prefix("error:") {
+			eprintln!("error: {}", error.trim());
+			failed = true;
+		} else if upper {
+			println!("{}{}", prefix, line.to_uppercase());
+		} else {
+			println!("{}{}", prefix, line);
+		}
+	}
+
+	if failed {
+		std::process::exit(2);
+	}
+}
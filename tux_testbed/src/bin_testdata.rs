@@ -20,10 +20,22 @@ fn main() {
 	let arg_callback = &args[0];
 	let arg_testdata_dir = &args[1];
 
+	if arg_callback == "bin" {
+		tux::testdata_bin("bin_filter", arg_testdata_dir);
+		return;
+	}
+
+	if arg_callback == "fallible" {
+		tux::testdata_fallible(arg_testdata_dir, callback_fallible);
+		return;
+	}
+
 	let callback = match arg_callback.as_str() {
 		"empty" => callback_empty,
 		"reverse" => callback_reverse,
 		"id" => callback_id,
+		"panic" => callback_panic,
+		"slow" => callback_slow,
 		_ => {
 			eprintln!("invalid function: {}\n", args[0]);
 			print_usage();
@@ -45,10 +57,32 @@ fn main() {
 	fn callback_id(input: Vec<String>) -> Vec<String> {
 		input
 	}
+
+	fn callback_fallible(input: Vec<String>) -> Result<Vec<String>, String> {
+		if input.iter().any(|x| x.contains("error")) {
+			Err(format!("callback failed with {}", input.join(" ")))
+		} else {
+			Ok(input)
+		}
+	}
+
+	fn callback_slow(input: Vec<String>) -> Vec<String> {
+		if input.iter().any(|x| x.contains("slow")) {
+			std::thread::sleep(std::time::Duration::from_millis(500));
+		}
+		input
+	}
+
+	fn callback_panic(input: Vec<String>) -> Vec<String> {
+		if input.iter().any(|x| x.contains("panic")) {
+			panic!("callback panicked");
+		}
+		input
+	}
 }
 
 fn print_usage() {
 	println!("Executes the testdata tests in the given directory, using the given function.\n");
 	println!("This is used as part of the test harness for tux.\n");
-	println!("Usage: (empty|reverse|id) DIRECTORY");
+	println!("Usage: (empty|reverse|id|panic|slow|fallible|bin) DIRECTORY");
 }
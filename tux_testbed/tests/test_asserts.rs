@@ -6,3 +6,13 @@ mod panics {
 		assert_panic!("panicked" in panic!("panicked"));
 	}
 }
+
+mod eq_diff {
+	use tux::{assert_eq_diff, assert_panic};
+
+	#[test]
+	fn works_outside_the_crate() {
+		assert_eq_diff!(vec![1, 2], vec![1, 2]);
+		assert_panic!("-    2,\n+    3," in assert_eq_diff!(vec![1, 2], vec![1, 3]));
+	}
+}
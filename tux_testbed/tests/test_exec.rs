@@ -37,6 +37,17 @@ mod run_bin {
 		assert_panic!("exited with error" in run());
 		assert_panic!("123" in run());
 	}
+
+	#[test]
+	fn applies_bound_redactions_to_output() {
+		let redactions = tux::redact::Redactions::new().rule("simple", "simple", "[NAME]");
+		let output = redactions.bind(|| run_bin("bin_simple", &[]));
+		assert!(
+			output.contains("tux [NAME] output"),
+			"unexpected output: {}",
+			output
+		);
+	}
 }
 
 mod get_process_output {
@@ -56,7 +67,7 @@ mod get_process_output {
 	fn panics_on_non_zero_exit_code() {
 		let run = || {
 			let mut cmd = get_bin("bin_with_error");
-			cmd.args(&["exitcode"]);
+			cmd.args(["exitcode"]);
 			get_process_output(cmd.output().unwrap());
 		};
 		assert_panic!("123" in run());
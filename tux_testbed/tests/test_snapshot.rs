@@ -0,0 +1,13 @@
+mod snapshot {
+	use tux::assert_snapshot;
+
+	#[test]
+	fn matches_snapshot_next_to_the_test_file() {
+		assert_snapshot!("simple_value", "line 1\nline 2");
+	}
+
+	#[test]
+	fn matches_inline_snapshot() {
+		assert_snapshot!(format!("{:?}", (1, "a")), @r#"(1, "a")"#);
+	}
+}
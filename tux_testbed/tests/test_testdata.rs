@@ -1,14 +1,35 @@
+use std::{path::Path, process::Command};
+
 use tux::*;
 
+/// Returns a configuration for the test directory that ignores the environment
+/// variables changing the test outcome (e.g. `TUX_BLESS`).
+fn test_data<P: AsRef<Path>>(path: P) -> TestData {
+	TestData::new(path).bless(false).strict(false).filter("")
+}
+
+/// Returns the command for the `bin_testdata` helper, without the environment
+/// variables changing the test outcome (e.g. `TUX_BLESS`).
+fn get_testdata_bin() -> Command {
+	let mut cmd = get_bin("bin_testdata");
+	cmd.env_remove(TESTDATA_BLESS_VAR)
+		.env_remove(TESTDATA_STRICT_VAR)
+		.env_remove(TESTDATA_FILTER_VAR)
+		.env_remove("CARGO_TERM_COLOR")
+		.env_remove("CLICOLOR_FORCE");
+	cmd
+}
+
 mod testdata {
-	use super::get_bin;
+	use super::get_process_output;
+	use super::get_testdata_bin;
 	use super::run_bin;
 	use super::temp_dir;
-	use super::testdata;
+	use super::test_data;
 
 	#[test]
 	fn successful_case_does_not_panic() {
-		testdata("tests/testdata/reverse", |mut input| {
+		test_data("tests/testdata/reverse").run(|mut input| {
 			input.reverse();
 			input
 		});
@@ -17,7 +38,7 @@ mod testdata {
 	#[test]
 	#[should_panic = "test case failed"]
 	fn failed_case_panics() {
-		testdata("tests/testdata/failed", |input| input);
+		test_data("tests/testdata/failed").run(|input| input);
 	}
 
 	#[test]
@@ -28,7 +49,7 @@ mod testdata {
 		dir.create_file("b.input", "");
 		dir.create_file("a.valid", "fail");
 		dir.create_file("b.valid", "fail");
-		testdata(dir.path(), |input| input);
+		test_data(dir.path()).run(|input| input);
 	}
 
 	#[test]
@@ -50,7 +71,11 @@ mod testdata {
 		dir.create_file("c.valid", "");
 		dir.create_file("sub/some.valid", "");
 
-		let output = run_bin("bin_testdata", &["empty", dir.path_str()]);
+		let output = get_testdata_bin()
+			.args(["empty", dir.path_str()])
+			.output()
+			.unwrap();
+		let output = get_process_output(output);
 
 		let output = output
 			.lines()
@@ -73,8 +98,8 @@ mod testdata {
 		dir.create_file("pass.valid", "");
 		dir.create_file("fail.valid", "this will fail");
 
-		let output = get_bin("bin_testdata")
-			.args(&["empty", dir.path_str()])
+		let output = get_testdata_bin()
+			.args(["empty", dir.path_str()])
 			.output()
 			.unwrap();
 		let output = String::from_utf8_lossy(&output.stdout)
@@ -95,14 +120,14 @@ mod testdata {
 		dir.create_file("pass.valid", "");
 		dir.create_file("fail.valid", "this will fail");
 
-		let output = get_bin("bin_testdata")
-			.args(&["empty", dir.path_str()])
+		let output = get_testdata_bin()
+			.args(["empty", dir.path_str()])
 			.output()
 			.unwrap();
 		let stderr = String::from_utf8_lossy(&output.stderr);
 		let output = stderr
 			.lines()
-			.filter(|x| x.contains("Failed") || (x.starts_with("-") && x.contains(".input")))
+			.filter(|x| x.contains("Failed") || (x.starts_with("- ") && x.contains(".input")))
 			.collect::<Vec<_>>();
 		assert_eq!(
 			output,
@@ -114,8 +139,8 @@ mod testdata {
 
 	#[test]
 	fn outputs_diff_for_failed_test() {
-		let test_result = get_bin("bin_testdata")
-			.args(&["id", "tests/testdata/failed_diff"])
+		let test_result = get_testdata_bin()
+			.args(["id", "tests/testdata/failed_diff"])
 			.output()
 			.unwrap();
 
@@ -128,12 +153,12 @@ mod testdata {
 			diff_lines,
 			vec![
 				"=> `test1.input` output did not match `test1.valid`:",
-				"-test line A",
-				"+test line 1",
+				"-test line [-A-]",
+				"+test line {+1+}",
 				" test line 2",
 				" test line 3",
-				"-test line B",
-				"+test line 4",
+				"-test line [-B-]",
+				"+test line {+4+}",
 				"+test line 5",
 				" test line 6",
 				"+test line 7",
@@ -141,21 +166,52 @@ mod testdata {
 				" test line 9",
 				"-test line C",
 				"=> `test2.input` output did not match `test2.valid`:",
-				"-test line A",
-				"+test line B",
+				"-test line [-A-]",
+				"+test line {+B+}",
 			],
 			"expected failed test to output diff, but it was:\n\n----\n{}\n----\n",
 			stderr,
 		);
 	}
 
+	#[test]
+	fn outputs_unified_diff_headers_for_failed_test() {
+		let test_result = get_testdata_bin()
+			.args(["id", "tests/testdata/failed_diff"])
+			.output()
+			.unwrap();
+
+		let stderr = String::from_utf8_lossy(&test_result.stderr);
+		let header_lines = stderr
+			.lines()
+			.filter(|x| x.starts_with("---") || x.starts_with("+++") || x.starts_with("@@"))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			header_lines,
+			vec![
+				"--- test1.input",
+				"+++ test1.valid",
+				"@@ -1,8 +1,9 @@",
+				"--- test2.input",
+				"+++ test2.valid",
+				"@@ -1 +1 @@",
+			],
+			"expected unified diff headers, but it was:\n\n----\n{}\n----\n",
+			stderr,
+		);
+	}
+
 	#[test]
 	fn does_not_output_diff_for_missing_valid_file_but_creates_new_file_and_then_fails() {
 		let dir = temp_dir();
 		dir.create_file("a.input", "input line 1\ninput line 2");
 		dir.create_file("b.input", "input line A\ninput line B");
 
-		let output = dir.get_bin_output("bin_testdata", &["id", "."]);
+		let output = get_testdata_bin()
+			.args(["id", "."])
+			.current_dir(dir.path())
+			.output()
+			.unwrap();
 
 		let exit_status_is_non_zero = !output.status.success();
 		assert!(exit_status_is_non_zero);
@@ -181,4 +237,418 @@ mod testdata {
 		assert!(stderr.contains("created `a.valid.new` with the current test output"));
 		assert!(stderr.contains("created `b.valid.new`"));
 	}
+
+	#[test]
+	fn bless_mode_updates_valid_files_and_reports_them() {
+		let dir = temp_dir();
+		dir.create_file("pass.input", "same");
+		dir.create_file("pass.valid", "same");
+		dir.create_file("fail.input", "new output");
+		dir.create_file("fail.valid", "old output");
+
+		let output = get_testdata_bin()
+			.args(["id", dir.path_str()])
+			.env("TUX_BLESS", "1")
+			.output()
+			.unwrap();
+		assert!(output.status.success());
+
+		let stdout = String::from_utf8_lossy(&output.stdout);
+		assert!(stdout.contains("blessed: fail.input"));
+		assert!(stdout.contains("passed: pass.input"));
+
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		assert!(
+			stderr.contains("- fail.valid (test fail.input)"),
+			"expected updated files in stderr, but it was:\n\n----\n{}\n----\n",
+			stderr
+		);
+
+		let valid = std::fs::read_to_string(dir.path().join("fail.valid")).unwrap();
+		assert_eq!(valid, "new output");
+	}
+
+	#[test]
+	fn filter_runs_matching_cases_and_reports_skipped() {
+		let dir = temp_dir();
+		dir.create_file("parser/if.input", "a");
+		dir.create_file("parser/if.valid", "a");
+		dir.create_file("parser/while.input", "fail");
+		dir.create_file("parser/while.valid", "this will fail");
+		dir.create_file("lexer/if.input", "fail");
+		dir.create_file("lexer/if.valid", "this will fail");
+
+		let output = get_testdata_bin()
+			.args(["id", dir.path_str()])
+			.env("TUX_TESTDATA_FILTER", "parser/if*")
+			.output()
+			.unwrap();
+		assert!(output.status.success());
+
+		let stdout = String::from_utf8_lossy(&output.stdout);
+		assert!(stdout.contains("passed: parser/if.input"));
+		assert!(!stdout.contains("while.input"));
+		assert!(!stdout.contains("lexer/if.input"));
+		assert!(
+			stdout.contains("skipped: 2 test cases not matching filter `parser/if*`"),
+			"expected skipped cases in stdout, but it was:\n\n----\n{}\n----\n",
+			stdout
+		);
+	}
+
+	#[test]
+	fn reports_panics_in_callback_as_failed_cases() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "panic");
+		dir.create_file("a.valid", "panic");
+		dir.create_file("b.input", "ok");
+		dir.create_file("b.valid", "ok");
+
+		let output = get_testdata_bin()
+			.args(["panic", dir.path_str()])
+			.output()
+			.unwrap();
+		assert!(!output.status.success());
+
+		let stdout = String::from_utf8_lossy(&output.stdout);
+		assert!(stdout.contains("failed: a.input"));
+		assert!(stdout.contains("passed: b.input"));
+
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		assert!(
+			stderr.contains("=> `a.input` panicked: callback panicked (at "),
+			"expected panic message in stderr, but it was:\n\n----\n{}\n----\n",
+			stderr
+		);
+		assert!(stderr.contains("- a.input (panicked)"));
+		assert!(stderr.contains("1 test case failed"));
+
+		// the default panic hook output is suppressed for the callback
+		assert_eq!(stderr.matches("callback panicked").count(), 1);
+	}
+
+	#[test]
+	fn reports_unexpected_errors_for_fallible_callback() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "error a");
+		dir.create_file("a.valid", "error a");
+		dir.create_file("b.input", "b");
+		dir.create_file("b.error", "some error");
+		dir.create_file("c.input", "error c");
+		dir.create_file("c.error", "callback failed with error c");
+
+		let output = get_testdata_bin()
+			.args(["fallible", dir.path_str()])
+			.output()
+			.unwrap();
+		assert!(!output.status.success());
+
+		let stdout = String::from_utf8_lossy(&output.stdout);
+		assert!(stdout.contains("failed: a.input"));
+		assert!(stdout.contains("failed: b.input"));
+		assert!(stdout.contains("passed: c.input"));
+
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		assert!(
+			stderr.contains(
+				"=> `a.input` failed with an error, but `a.valid` expects an output:\n\n    callback failed with error a"
+			),
+			"expected unexpected error in stderr, but it was:\n\n----\n{}\n----\n",
+			stderr
+		);
+		assert!(stderr.contains("=> `b.input` succeeded, but `b.error` expects an error"));
+		assert!(stderr.contains("2 test cases failed"));
+	}
+
+	#[test]
+	fn reports_skipped_and_expected_failures() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "// tux: skip=not ready\na");
+		dir.create_file("b.input", "// tux: xfail\nb");
+		dir.create_file("b.valid", "this will fail");
+		dir.create_file("c.input", "c");
+		dir.create_file("c.valid", "c");
+
+		let output = get_testdata_bin()
+			.args(["id", dir.path_str()])
+			.output()
+			.unwrap();
+		assert!(output.status.success());
+
+		let stdout = String::from_utf8_lossy(&output.stdout);
+		assert!(stdout.contains("skipped: a.input"));
+		assert!(stdout.contains("expected failure: b.input"));
+		assert!(stdout.contains("passed: c.input"));
+
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		assert!(
+			stderr.contains("===== Skipped tests =====\n\n- a.input (not ready)"),
+			"expected skipped tests in stderr, but it was:\n\n----\n{}\n----\n",
+			stderr
+		);
+		assert!(stderr.contains("===== Expected failures =====\n\n- b.input"));
+	}
+
+	#[test]
+	fn reports_orphaned_files_as_warnings_or_failures() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "a");
+		dir.create_file("a.valid", "a");
+		dir.create_file("b.valid", "b");
+
+		let output = get_testdata_bin()
+			.args(["id", dir.path_str()])
+			.output()
+			.unwrap();
+		assert!(output.status.success());
+
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		assert!(
+			stderr.contains(
+				"===== Warnings =====\n\n- `b.valid` is orphaned, since `b.input` does not exist"
+			),
+			"expected warning in stderr, but it was:\n\n----\n{}\n----\n",
+			stderr
+		);
+
+		let output = get_testdata_bin()
+			.args(["id", dir.path_str()])
+			.env("TUX_TESTDATA_STRICT", "1")
+			.output()
+			.unwrap();
+		assert!(!output.status.success());
+
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		assert!(
+			stderr.contains("=> `b.valid` is orphaned, since `b.input` does not exist"),
+			"expected failure in stderr, but it was:\n\n----\n{}\n----\n",
+			stderr
+		);
+	}
+
+	#[test]
+	fn fails_slow_case_on_timeout_and_continues() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "a");
+		dir.create_file("a.valid", "a");
+		dir.create_file("b.input", "// tux: timeout=100ms\nslow");
+		dir.create_file("b.valid", "slow");
+		dir.create_file("c.input", "c");
+		dir.create_file("c.valid", "c");
+
+		let output = get_testdata_bin()
+			.args(["slow", dir.path_str()])
+			.output()
+			.unwrap();
+		assert!(!output.status.success());
+
+		let stdout = String::from_utf8_lossy(&output.stdout);
+		assert!(stdout.contains("passed: c.input"));
+
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		assert!(
+			stderr.contains("=> `b.input` is still running after the 100ms timeout"),
+			"expected stuck case in stderr, but it was:\n\n----\n{}\n----\n",
+			stderr
+		);
+		assert!(stderr.contains("=> `b.input` timed out after 100ms"));
+	}
+
+	#[test]
+	fn writes_reports_to_report_dir() {
+		let dir = temp_dir();
+		dir.create_file("cases/a.input", "a");
+		dir.create_file("cases/a.valid", "a");
+		dir.create_file("cases/b.input", "b");
+		dir.create_file("cases/b.valid", "fail");
+
+		let output = get_testdata_bin()
+			.args(["id", "cases"])
+			.current_dir(dir.path())
+			.env("TUX_REPORT_DIR", "reports")
+			.output()
+			.unwrap();
+		assert!(!output.status.success());
+
+		let json = std::fs::read_to_string(dir.path().join("reports/cases.json")).unwrap();
+		assert!(
+			json.contains("\"name\": \"b.input\",\n      \"status\": \"failed\""),
+			"expected failed case in JSON report, but it was:\n\n----\n{}\n----\n",
+			json
+		);
+
+		let xml = std::fs::read_to_string(dir.path().join("reports/cases.xml")).unwrap();
+		assert!(xml.contains("<testsuite name=\"cases\" tests=\"2\" failures=\"1\""));
+	}
+}
+
+mod testdata_bin {
+	use super::assert_panic;
+	use super::get_testdata_bin;
+	use super::temp_dir;
+	use super::test_data;
+	use std::time::Duration;
+
+	#[test]
+	fn runs_binary_with_input_args_and_env() {
+		let dir = temp_dir();
+		dir.create_file("plain.input", "a\nb");
+		dir.create_file("plain.stdout", "a\nb");
+		dir.create_file("upper.input", "a\nb");
+		dir.create_file("upper.args", "# arguments\n--upper");
+		dir.create_file("upper.env", "FILTER_PREFIX=> \n");
+		dir.create_file("upper.stdout", "> A\n> B");
+		test_data(dir.path()).run_bin("bin_filter");
+	}
+
+	#[test]
+	fn compares_error_output_and_exit_status() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "a\nerror: some error");
+		dir.create_file("a.stdout", "a");
+		dir.create_file("a.stderr", "error: some error");
+		dir.create_file("a.status", "2");
+		test_data(dir.path()).run_bin("bin_filter");
+
+		// no `.stderr` and `.status` files expect a successful run
+		std::fs::remove_file(dir.path().join("a.stderr")).unwrap();
+		std::fs::remove_file(dir.path().join("a.status")).unwrap();
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in test_data(&path).run_bin("bin_filter"));
+	}
+
+	#[test]
+	fn outputs_diff_for_each_mismatched_output() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "a\nerror: some error");
+		dir.create_file("a.stdout", "a");
+		dir.create_file("a.stderr", "error: other error");
+
+		let output = get_testdata_bin()
+			.args(["bin", dir.path_str()])
+			.output()
+			.unwrap();
+		assert!(!output.status.success());
+
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		let diff_lines = stderr
+			.lines()
+			.filter(|x| x.starts_with(['=', '-', '+']) && !x.starts_with("=="))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			diff_lines,
+			vec![
+				"=> `a.input` output did not match `a.stderr`:",
+				"--- a.input (stderr)",
+				"+++ a.stderr",
+				"-error: [-some-] error",
+				"+error: {+other+} error",
+				"--- a.input (status)",
+				"+++ a.status",
+				"-2",
+				"+0",
+				"- a.input",
+			],
+			"expected a diff for each output, but it was:\n\n----\n{}\n----\n",
+			stderr,
+		);
+	}
+
+	#[test]
+	fn creates_new_stdout_file_if_missing() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "some output");
+
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in test_data(&path).run_bin("bin_filter"));
+		let new_file = std::fs::read_to_string(dir.path().join("a.stdout.new")).unwrap();
+		assert_eq!(new_file, "some output");
+		assert!(!dir.path().join("a.stdout").exists());
+	}
+
+	#[test]
+	fn reports_other_mismatches_with_missing_stdout_file() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "a\nerror: some error");
+		dir.create_file("a.stderr", "error: some error");
+		dir.create_file("a.status", "1");
+
+		let output = get_testdata_bin()
+			.args(["bin", dir.path_str()])
+			.output()
+			.unwrap();
+		assert!(!output.status.success());
+
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		let diff_lines = stderr
+			.lines()
+			.filter(|x| x.starts_with(['=', '-', '+', '.']) && !x.starts_with("=="))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			diff_lines,
+			vec![
+				"=> `a.input` output did not match `a.status`:",
+				".. `a.stdout` not found, created `a.stdout.new` with the current output",
+				"--- a.input (status)",
+				"+++ a.status",
+				"-2",
+				"+1",
+				"- a.input",
+			],
+			"expected the missing file and the status diff, but it was:\n\n----\n{}\n----\n",
+			stderr,
+		);
+		let new_file = std::fs::read_to_string(dir.path().join("a.stdout.new")).unwrap();
+		assert_eq!(new_file, "a");
+	}
+
+	#[test]
+	fn keeps_mismatched_stdout_file_if_not_blessed() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "new output");
+		dir.create_file("a.stdout", "old output");
+
+		// the case must keep failing until the output is blessed
+		for _ in 0..2 {
+			let path = dir.path().to_owned();
+			assert_panic!("1 test case failed" in test_data(&path).run_bin("bin_filter"));
+		}
+		let stdout = std::fs::read_to_string(dir.path().join("a.stdout")).unwrap();
+		assert_eq!(stdout, "old output");
+		assert!(!dir.path().join("a.stdout.new").exists());
+	}
+
+	#[test]
+	fn kills_binary_on_timeout() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "sleep: 10");
+		dir.create_file("a.stdout", "");
+		dir.create_file("b.input", "b");
+		dir.create_file("b.stdout", "b");
+
+		let start = std::time::Instant::now();
+		let path = dir.path().to_owned();
+		assert_panic!("1 test case failed" in test_data(&path)
+			.timeout(Duration::from_millis(200))
+			.run_bin("bin_filter"));
+		assert!(start.elapsed() < Duration::from_secs(5));
+	}
+
+	#[test]
+	fn bless_mode_updates_expected_files() {
+		let dir = temp_dir();
+		dir.create_file("a.input", "new output\nerror: some error");
+		dir.create_file("a.stdout", "old output");
+		dir.create_file("b.input", "b");
+		dir.create_file("b.stdout", "b");
+		dir.create_file("b.status", "2");
+
+		test_data(dir.path()).bless(true).run_bin("bin_filter");
+
+		let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+		assert_eq!(read("a.stdout"), "new output");
+		assert_eq!(read("a.stderr"), "error: some error");
+		assert_eq!(read("a.status"), "2");
+		assert!(!dir.path().join("b.status").exists());
+	}
 }
@@ -0,0 +1,8 @@
+//! Tests generated by the `testdata` attribute. Each case in the directory
+//! runs as a separate test (e.g. `reverse::list`).
+
+#[tux::testdata("tests/testdata/reverse")]
+fn reverse(mut lines: Vec<String>) -> Vec<String> {
+	lines.reverse();
+	lines
+}